@@ -0,0 +1,21 @@
+extern crate cc;
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    // leveldb's headers, as installed by leveldb-sys
+    let include = env::var_os("DEP_LEVELDB_INCLUDE")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("DEP_LEVELDB_ROOT").map(|root| PathBuf::from(root).join("include")))
+        .expect("leveldb-sys did not export the leveldb include directory");
+
+    cc::Build::new()
+        .cpp(true)
+        .flag_if_supported("-std=c++11")
+        .flag_if_supported("-fno-rtti")
+        .include(include)
+        .file("src/logger.cc")
+        .compile("leveldb_rs_logger");
+    println!("cargo:rerun-if-changed=src/logger.cc");
+}
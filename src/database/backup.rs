@@ -0,0 +1,145 @@
+//! Online backups
+//!
+//! A backup is a consistent copy of a database that can be opened like
+//! the original, taken while the database stays open and in use.
+//!
+//! leveldb only ever creates table files, never modifies them. Backups
+//! therefore hard-link table files where possible and only copy the small,
+//! changing files: the `MANIFEST` describing the current set of tables and
+//! the log of recent writes. Should the set of tables change while the
+//! backup is taken, e.g. due to a compaction, the backup is retried.
+//!
+//! Incremental backups reuse the table files of a previous backup instead
+//! of copying them from the database again, which helps when backing up
+//! to another file system.
+//!
+//! Backups must be opened with the same comparator as the database.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::Database;
+use super::key::Key;
+use super::error::{Error, ErrorKind, Operation};
+
+// how often to retry a backup of a database that keeps changing
+const ATTEMPTS: usize = 10;
+
+/// Backups of an open database
+pub trait Backup {
+    /// Write a backup of the database to `dest`.
+    ///
+    /// `dest` must be missing or an empty directory.
+    fn backup<P: AsRef<Path>>(&self, dest: P) -> Result<(), Error>;
+
+    /// Write a backup of the database to `dest`, reusing the table files
+    /// of the backup at `previous`.
+    ///
+    /// `dest` must be missing or an empty directory.
+    fn backup_incremental<P, Q>(&self, dest: P, previous: Q) -> Result<(), Error>
+        where P: AsRef<Path>,
+              Q: AsRef<Path>;
+}
+
+impl<K: Key> Backup for Database<K> {
+    fn backup<P: AsRef<Path>>(&self, dest: P) -> Result<(), Error> {
+        backup(&self.path, dest.as_ref(), None)
+    }
+
+    fn backup_incremental<P, Q>(&self, dest: P, previous: Q) -> Result<(), Error>
+        where P: AsRef<Path>,
+              Q: AsRef<Path>
+    {
+        backup(&self.path, dest.as_ref(), Some(previous.as_ref()))
+    }
+}
+
+fn backup(source: &Path, dest: &Path, previous: Option<&Path>) -> Result<(), Error> {
+    let backup_error = |kind, message| {
+        Error::new_with_kind(kind, format!("backup failed: {}", message))
+            .with_operation(Operation::Backup)
+            .with_path(dest)
+    };
+    let io_error = |e: io::Error| backup_error(ErrorKind::IoError, e.to_string());
+
+    fs::create_dir_all(dest).map_err(io_error)?;
+    if fs::read_dir(dest).map_err(io_error)?.next().is_some() {
+        return Err(backup_error(ErrorKind::InvalidArgument, "destination is not empty".to_string()));
+    }
+
+    for _ in 0..ATTEMPTS {
+        match try_backup(source, dest, previous) {
+            Ok(true) => return Ok(()),
+            // a file was removed by a compaction, try again
+            Ok(false) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(io_error(e)),
+        }
+        clear_dir(dest).map_err(io_error)?;
+    }
+    Err(backup_error(ErrorKind::Other, "the database kept changing".to_string()))
+}
+
+/// Copy the current state of the database, returning whether it stayed
+/// unchanged while doing so.
+fn try_backup(source: &Path, dest: &Path, previous: Option<&Path>) -> io::Result<bool> {
+    let manifest = read_current(source)?;
+    let manifest_bytes = fs::read(source.join(&manifest))?;
+    fs::write(dest.join(&manifest), &manifest_bytes)?;
+
+    let mut logs = vec![];
+    for entry in fs::read_dir(source)? {
+        let name = entry?.file_name();
+        let name = match name.to_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if name.ends_with(".ldb") || name.ends_with(".sst") {
+            copy_table(source, dest, previous, &name)?;
+        } else if name.ends_with(".log") {
+            logs.push(name);
+        }
+    }
+    // logs are copied last, so they hold all writes not in the tables
+    for name in logs {
+        fs::copy(source.join(&name), dest.join(&name))?;
+    }
+
+    // any change to the set of tables is recorded in the manifest
+    if read_current(source)? != manifest ||
+       fs::metadata(source.join(&manifest))?.len() != manifest_bytes.len() as u64 {
+        return Ok(false);
+    }
+    fs::write(dest.join("CURRENT"), format!("{}\n", manifest))?;
+    Ok(true)
+}
+
+fn read_current(dir: &Path) -> io::Result<String> {
+    fs::read_to_string(dir.join("CURRENT")).map(|s| s.trim_end().to_string())
+}
+
+fn copy_table(source: &Path, dest: &Path, previous: Option<&Path>, name: &str) -> io::Result<()> {
+    let from = source.join(name);
+    if let Some(previous) = previous {
+        let reused = previous.join(name);
+        let unchanged = match (fs::metadata(&reused), fs::metadata(&from)) {
+            (Ok(a), Ok(b)) => a.len() == b.len(),
+            _ => false,
+        };
+        if unchanged {
+            return link_or_copy(&reused, &dest.join(name));
+        }
+    }
+    link_or_copy(&from, &dest.join(name))
+}
+
+fn link_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    fs::hard_link(from, to).or_else(|_| fs::copy(from, to).map(|_| ()))
+}
+
+fn clear_dir(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        fs::remove_file(entry?.path())?;
+    }
+    Ok(())
+}
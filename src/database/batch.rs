@@ -7,9 +7,23 @@ use database::key::Key;
 use database::key::from_u8;
 use std::slice;
 use options::{WriteOptions, c_writeoptions};
-use super::error::Error;
+use super::error::{Error, ErrorKind, Operation};
 use std::ptr;
+use std::vec;
 use super::Database;
+use super::panic::{catch_local, resume_local};
+
+extern "C" {
+    // part of the leveldb C API, but not declared by leveldb-sys
+    fn leveldb_writebatch_append(destination: *mut leveldb_writebatch_t,
+                                 source: *const leveldb_writebatch_t);
+}
+
+// the size of the sequence number and count header of a batch
+const HEADER_SIZE: usize = 12;
+// record tags of the batch representation
+const TAG_DELETE: u8 = 0;
+const TAG_PUT: u8 = 1;
 
 #[allow(missing_docs)]
 struct RawWritebatch {
@@ -28,6 +42,27 @@ impl Drop for RawWritebatch {
 pub struct Writebatch<K: Key> {
     #[allow(dead_code)]
     writebatch: RawWritebatch,
+    // kept up to date on every change, so they can be queried cheaply
+    len: usize,
+    size: usize,
+    marker: PhantomData<K>,
+}
+
+/// A single operation recorded in a `Writebatch`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOp<K> {
+    /// Put a value under a key
+    Put(K, Vec<u8>),
+    /// Delete a key
+    Delete(K),
+}
+
+// a raw key with its value, or `None` for a deletion
+type RawOp = (Vec<u8>, Option<Vec<u8>>);
+
+/// An iterator over the operations of a `Writebatch`, in the order they were recorded.
+pub struct BatchOps<K: Key> {
+    ops: vec::IntoIter<RawOp>,
     marker: PhantomData<K>,
 }
 
@@ -39,6 +74,16 @@ pub trait Batch<K: Key> {
 
 impl<K: Key> Batch<K> for Database<K> {
     fn write(&self, options: WriteOptions, batch: &Writebatch<K>) -> Result<(), Error> {
+        if self.changes.is_active() {
+            self.changes.commit(batch, || self.write_raw(options, batch))
+        } else {
+            self.write_raw(options, batch)
+        }
+    }
+}
+
+impl<K: Key> Database<K> {
+    fn write_raw(&self, options: WriteOptions, batch: &Writebatch<K>) -> Result<(), Error> {
         unsafe {
             let mut error = ptr::null_mut();
             let c_writeoptions = c_writeoptions(options);
@@ -50,9 +95,9 @@ impl<K: Key> Batch<K> for Database<K> {
             leveldb_writeoptions_destroy(c_writeoptions);
 
             if error == ptr::null_mut() {
-                Ok(())
+                self.check_panics(Operation::Write)
             } else {
-                Err(Error::new_from_i8(error))
+                Err(Error::new_from_i8(error).with_operation(Operation::Write).with_path(&self.path))
             }
         }
     }
@@ -65,6 +110,8 @@ impl<K: Key> Writebatch<K> {
         let raw = RawWritebatch { ptr: ptr };
         Writebatch {
             writebatch: raw,
+            len: 0,
+            size: HEADER_SIZE,
             marker: PhantomData,
         }
     }
@@ -72,30 +119,152 @@ impl<K: Key> Writebatch<K> {
     /// Clear the writebatch
     pub fn clear(&mut self) {
         unsafe { leveldb_writebatch_clear(self.writebatch.ptr) };
+        self.len = 0;
+        self.size = HEADER_SIZE;
     }
 
     /// Batch a put operation
     pub fn put(&mut self, key: K, value: &[u8]) {
-        unsafe {
-            key.as_slice(|k| {
-                leveldb_writebatch_put(self.writebatch.ptr,
-                                       k.as_ptr() as *mut c_char,
-                                       k.len() as size_t,
-                                       value.as_ptr() as *mut c_char,
-                                       value.len() as size_t);
-            })
-        }
+        let ptr = self.writebatch.ptr;
+        let key_len = key.as_slice(|k| {
+            batch_put(ptr, k, value);
+            k.len()
+        });
+        self.record(key_len, Some(value.len()));
     }
 
     /// Batch a delete operation
     pub fn delete(&mut self, key: K) {
+        let ptr = self.writebatch.ptr;
+        let key_len = key.as_slice(|k| {
+            batch_delete(ptr, k);
+            k.len()
+        });
+        self.record(key_len, None);
+    }
+
+    pub(crate) fn put_raw(&mut self, key: &[u8], value: &[u8]) {
+        batch_put(self.writebatch.ptr, key, value);
+        self.record(key.len(), Some(value.len()));
+    }
+
+    pub(crate) fn delete_raw(&mut self, key: &[u8]) {
+        batch_delete(self.writebatch.ptr, key);
+        self.record(key.len(), None);
+    }
+
+    // account for an operation in the serialized format: a tag byte and
+    // the length-prefixed key and value
+    fn record(&mut self, key_len: usize, value_len: Option<usize>) {
+        self.len += 1;
+        self.size += 1 + varint_len(key_len) + key_len;
+        if let Some(value_len) = value_len {
+            self.size += varint_len(value_len) + value_len;
+        }
+    }
+
+    /// Append all operations of `other` to this batch.
+    pub fn append(&mut self, other: &Writebatch<K>) {
+        unsafe { leveldb_writebatch_append(self.writebatch.ptr, other.writebatch.ptr) };
+        self.len += other.len;
+        self.size += other.size - HEADER_SIZE;
+    }
+
+    /// The number of operations in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the batch holds no operations.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The size of the batch in its serialized form, in bytes.
+    pub fn approximate_size(&self) -> usize {
+        self.size
+    }
+
+    /// Iterate over the operations of the batch.
+    pub fn ops(&self) -> BatchOps<K> {
+        let mut ops = vec![];
+        self.for_each_raw(&mut |key, value| ops.push((key.to_vec(), value.map(|v| v.to_vec()))));
+        BatchOps {
+            ops: ops.into_iter(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Serialize the batch into leveldb's batch representation.
+    ///
+    /// The format is a 12 byte header, holding a sequence number (always
+    /// 0 here) and the number of operations as little-endian fixed64 and
+    /// fixed32, followed by one record per operation: a tag byte (1 for
+    /// put, 0 for delete), the varint32 length prefixed key and, for puts,
+    /// the varint32 length prefixed value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_SIZE];
+        let mut count: u32 = 0;
+        self.for_each_raw(&mut |key, value| {
+            count += 1;
+            match value {
+                Some(value) => {
+                    bytes.push(TAG_PUT);
+                    put_length_prefixed(&mut bytes, key);
+                    put_length_prefixed(&mut bytes, value);
+                }
+                None => {
+                    bytes.push(TAG_DELETE);
+                    put_length_prefixed(&mut bytes, key);
+                }
+            }
+        });
+        bytes[8..HEADER_SIZE].copy_from_slice(&count.to_le_bytes());
+        bytes
+    }
+
+    /// Deserialize a batch produced by `to_bytes`.
+    ///
+    /// Fails if the bytes are not a well-formed batch representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Writebatch<K>, Error> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::new_with_kind(ErrorKind::Corruption, "malformed writebatch: too small".to_string()));
+        }
+        let mut count_bytes = [0; 4];
+        count_bytes.copy_from_slice(&bytes[8..HEADER_SIZE]);
+        let count = u32::from_le_bytes(count_bytes) as usize;
+
+        let mut batch = Writebatch::new();
+        let mut found = 0;
+        let mut input = &bytes[HEADER_SIZE..];
+        while let Some((&tag, rest)) = input.split_first() {
+            input = rest;
+            let key = get_length_prefixed(&mut input)?;
+            match tag {
+                TAG_PUT => {
+                    let value = get_length_prefixed(&mut input)?;
+                    batch.put_raw(key, value);
+                }
+                TAG_DELETE => batch.delete_raw(key),
+                _ => return Err(Error::new_with_kind(ErrorKind::Corruption, "malformed writebatch: unknown tag".to_string())),
+            }
+            found += 1;
+        }
+        if found != count {
+            return Err(Error::new_with_kind(ErrorKind::Corruption, "malformed writebatch: wrong count".to_string()));
+        }
+        Ok(batch)
+    }
+
+    fn for_each_raw(&self, f: RawVisitor<'_>) {
         unsafe {
-            key.as_slice(|k| {
-                leveldb_writebatch_delete(self.writebatch.ptr,
-                                          k.as_ptr() as *mut c_char,
-                                          k.len() as size_t);
-            })
+            let mut state = f;
+            leveldb_writebatch_iterate(self.writebatch.ptr,
+                                       &mut state as *mut _ as *mut c_void,
+                                       raw_put_callback,
+                                       raw_deleted_callback);
         }
+        resume_local();
     }
 
     /// Iterate over the writebatch, returning the resulting iterator
@@ -106,8 +275,107 @@ impl<K: Key> Writebatch<K> {
                                        iter as *mut c_void,
                                        put_callback::<K, T>,
                                        deleted_callback::<K, T>);
-            Box::from_raw(iter)
+            let iter = Box::from_raw(iter);
+            resume_local();
+            iter
+        }
+    }
+}
+
+fn batch_put(batch: *mut leveldb_writebatch_t, key: &[u8], value: &[u8]) {
+    unsafe {
+        leveldb_writebatch_put(batch,
+                               key.as_ptr() as *mut c_char,
+                               key.len() as size_t,
+                               value.as_ptr() as *mut c_char,
+                               value.len() as size_t);
+    }
+}
+
+fn batch_delete(batch: *mut leveldb_writebatch_t, key: &[u8]) {
+    unsafe {
+        leveldb_writebatch_delete(batch,
+                                  key.as_ptr() as *mut c_char,
+                                  key.len() as size_t);
+    }
+}
+
+impl<K: Key> Iterator for BatchOps<K> {
+    type Item = BatchOp<K>;
+
+    fn next(&mut self) -> Option<BatchOp<K>> {
+        self.ops.next().map(|(key, value)| {
+            match value {
+                Some(value) => BatchOp::Put(from_u8(&key), value),
+                None => BatchOp::Delete(from_u8(&key)),
+            }
+        })
+    }
+}
+
+fn varint_len(mut value: usize) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+fn put_length_prefixed(bytes: &mut Vec<u8>, data: &[u8]) {
+    let mut len = data.len();
+    while len >= 0x80 {
+        bytes.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    bytes.push(len as u8);
+    bytes.extend_from_slice(data);
+}
+
+fn get_length_prefixed<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let malformed = || Error::new_with_kind(ErrorKind::Corruption, "malformed writebatch: bad record".to_string());
+    let mut len: usize = 0;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = input.split_first().ok_or_else(malformed)?;
+        *input = rest;
+        if shift > 28 {
+            return Err(malformed());
+        }
+        len |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
         }
+        shift += 7;
+    }
+    if input.len() < len {
+        return Err(malformed());
+    }
+    let (data, rest) = input.split_at(len);
+    *input = rest;
+    Ok(data)
+}
+
+type RawVisitor<'a> = &'a mut dyn FnMut(&[u8], Option<&[u8]>);
+
+extern "C" fn raw_put_callback(state: *mut c_void,
+                               key: *const i8,
+                               keylen: size_t,
+                               val: *const i8,
+                               vallen: size_t) {
+    unsafe {
+        let f = &mut *(state as *mut RawVisitor);
+        let key_slice = slice::from_raw_parts::<u8>(key as *const u8, keylen);
+        let val_slice = slice::from_raw_parts::<u8>(val as *const u8, vallen);
+        catch_local(|| f(key_slice, Some(val_slice)), ());
+    }
+}
+
+extern "C" fn raw_deleted_callback(state: *mut c_void, key: *const i8, keylen: size_t) {
+    unsafe {
+        let f = &mut *(state as *mut RawVisitor);
+        let key_slice = slice::from_raw_parts::<u8>(key as *const u8, keylen);
+        catch_local(|| f(key_slice, None), ());
     }
 }
 
@@ -132,8 +400,10 @@ extern "C" fn put_callback<K: Key, T: WritebatchIterator<K = K>>(state: *mut c_v
         let iter: &mut T = &mut *(state as *mut T);
         let key_slice = slice::from_raw_parts::<u8>(key as *const u8, keylen as usize);
         let val_slice = slice::from_raw_parts::<u8>(val as *const u8, vallen as usize);
-        let k = from_u8::<<T as WritebatchIterator>::K>(key_slice);
-        iter.put(k, val_slice);
+        catch_local(|| {
+            let k = from_u8::<<T as WritebatchIterator>::K>(key_slice);
+            iter.put(k, val_slice)
+        }, ());
     }
 }
 
@@ -143,7 +413,9 @@ extern "C" fn deleted_callback<K: Key, T: WritebatchIterator<K = K>>(state: *mut
     unsafe {
         let iter: &mut T = &mut *(state as *mut T);
         let key_slice = slice::from_raw_parts::<u8>(key as *const u8, keylen as usize);
-        let k = from_u8::<<T as WritebatchIterator>::K>(key_slice);
-        iter.deleted(k);
+        catch_local(|| {
+            let k = from_u8::<<T as WritebatchIterator>::K>(key_slice);
+            iter.deleted(k)
+        }, ());
     }
 }
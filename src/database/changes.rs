@@ -0,0 +1,394 @@
+//! Change feeds
+//!
+//! Listeners subscribed to a database are notified of every committed
+//! mutation, be it through `KV::put`, `KV::delete` or `Batch::write`. Each
+//! commit is published as a `Writebatch` together with a sequence number
+//! that increases by one with every commit, in the order the commits were
+//! applied.
+//!
+//! Listeners are first asked to `prepare` a commit before it is applied to
+//! the database, which allows them to record it ahead of time, like
+//! `Changelog` does. A listener failing to prepare aborts the commit.
+//!
+//! Sequence numbers are counted by the `Database` object and start at 0
+//! when it is opened. Subscribing a listener that already recorded changes,
+//! e.g. an existing `Changelog`, continues after its last sequence number.
+//! `Changes::set_last_sequence` sets the sequence explicitly.
+//!
+//! Listeners run while further commits are held back. They must not write
+//! to the database they are subscribed to; such writes fail with
+//! `ErrorKind::InvalidArgument`.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError, RwLock};
+use std::thread::{self, ThreadId};
+
+use super::Database;
+use super::key::Key;
+use super::batch::Writebatch;
+use super::error::{Error, ErrorKind};
+
+/// A listener notified of committed mutations.
+pub trait ChangeListener<K: Key>: Send + Sync {
+    /// Called before `batch` is committed to the database.
+    ///
+    /// Returning an error aborts the commit, which then fails with that
+    /// error.
+    fn prepare(&self, _sequence: u64, _batch: &Writebatch<K>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after `batch` has been committed to the database.
+    ///
+    /// Listeners are called while further commits are held back, so they
+    /// should return quickly.
+    fn committed(&self, _sequence: u64, _batch: &Writebatch<K>) {}
+
+    /// Called instead of `committed` if the commit failed after `prepare`
+    /// succeeded.
+    fn aborted(&self, _sequence: u64, _batch: &Writebatch<K>) {}
+
+    /// The sequence number of the last commit the listener recorded before
+    /// it was subscribed, if any.
+    fn last_sequence(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl<K: Key, F> ChangeListener<K> for F
+    where F: Fn(u64, &Writebatch<K>) + Send + Sync
+{
+    fn committed(&self, sequence: u64, batch: &Writebatch<K>) {
+        self(sequence, batch)
+    }
+}
+
+/// Subscription to the changes of a database.
+pub trait Changes<K: Key> {
+    /// Notify `listener` of all mutations committed from now on.
+    ///
+    /// If the listener recorded changes before, numbering continues after
+    /// its last sequence number.
+    fn subscribe<L: ChangeListener<K> + 'static>(&self, listener: L);
+
+    /// The sequence number of the last published commit.
+    fn last_sequence(&self) -> u64;
+
+    /// Continue numbering commits after `sequence`.
+    fn set_last_sequence(&self, sequence: u64);
+}
+
+/// The listeners and sequence counter of a database.
+pub(crate) struct ChangeFeed<K: Key> {
+    listeners: RwLock<Vec<Box<dyn ChangeListener<K>>>>,
+    // held while committing, so sequence numbers follow the commit order
+    sequence: Mutex<u64>,
+    // the thread currently committing, to reject writes from listeners
+    committer: Mutex<Option<ThreadId>>,
+}
+
+// Clears the committing thread once a commit is done, even if a listener
+// panicked.
+struct Committing<'a> {
+    committer: &'a Mutex<Option<ThreadId>>,
+}
+
+impl<'a> Drop for Committing<'a> {
+    fn drop(&mut self) {
+        *self.committer.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+impl<K: Key> ChangeFeed<K> {
+    pub(crate) fn new() -> ChangeFeed<K> {
+        ChangeFeed {
+            listeners: RwLock::new(vec![]),
+            sequence: Mutex::new(0),
+            committer: Mutex::new(None),
+        }
+    }
+
+    /// Whether any listener is subscribed.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.listeners.read().unwrap_or_else(PoisonError::into_inner).is_empty()
+    }
+
+    /// Commit `batch` using `write` and publish it to all listeners.
+    pub(crate) fn commit<F>(&self, batch: &Writebatch<K>, write: F) -> Result<(), Error>
+        where F: FnOnce() -> Result<(), Error>
+    {
+        let current = Some(thread::current().id());
+        if *self.committer.lock().unwrap_or_else(PoisonError::into_inner) == current {
+            // waiting for the sequence would deadlock
+            return Err(Error::new_with_kind(ErrorKind::InvalidArgument,
+                                            "change listeners must not write to their database"
+                                                .to_string()));
+        }
+
+        let mut sequence = self.sequence.lock().unwrap_or_else(PoisonError::into_inner);
+        *self.committer.lock().unwrap_or_else(PoisonError::into_inner) = current;
+        let _committing = Committing { committer: &self.committer };
+
+        let next = *sequence + 1;
+        let listeners = self.listeners.read().unwrap_or_else(PoisonError::into_inner);
+        for (i, listener) in listeners.iter().enumerate() {
+            if let Err(e) = listener.prepare(next, batch) {
+                for prepared in &listeners[..i] {
+                    prepared.aborted(next, batch);
+                }
+                return Err(e);
+            }
+        }
+        if let Err(e) = write() {
+            for listener in listeners.iter() {
+                listener.aborted(next, batch);
+            }
+            return Err(e);
+        }
+        *sequence = next;
+        for listener in listeners.iter() {
+            listener.committed(next, batch);
+        }
+        Ok(())
+    }
+}
+
+impl<K: Key> Changes<K> for Database<K> {
+    fn subscribe<L: ChangeListener<K> + 'static>(&self, listener: L) {
+        let mut sequence = self.changes.sequence.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(last) = listener.last_sequence() {
+            if last > *sequence {
+                *sequence = last;
+            }
+        }
+        self.changes.listeners.write().unwrap_or_else(PoisonError::into_inner).push(Box::new(listener));
+    }
+
+    fn last_sequence(&self) -> u64 {
+        *self.changes.sequence.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_last_sequence(&self, sequence: u64) {
+        *self.changes.sequence.lock().unwrap_or_else(PoisonError::into_inner) = sequence;
+    }
+}
+
+/// A write-ahead log of all changes.
+///
+/// Each commit is appended and synced to disk before it is applied to the
+/// database, and removed again if applying it fails. A commit that cannot
+/// be logged fails with the I/O error.
+///
+/// Each record consists of the sequence number as little-endian u64, the
+/// length of the batch as little-endian u32, a CRC-32C checksum of the
+/// preceding fields and the batch as little-endian u32, followed by the
+/// batch in the format of `Writebatch::to_bytes`.
+///
+/// A record that is incomplete or fails its checksum, e.g. because a crash
+/// interrupted writing it, ends the log.
+pub struct Changelog {
+    path: PathBuf,
+    state: Mutex<LogState>,
+    last_sequence: Option<u64>,
+}
+
+struct LogState {
+    file: File,
+    // the length of the log without the pending record
+    len: u64,
+    // the sequence number and length of the record written by `prepare`,
+    // awaiting the outcome of its commit
+    pending: Option<(u64, u64)>,
+}
+
+const RECORD_HEADER_SIZE: usize = 16;
+
+impl Changelog {
+    /// Open the changelog at `path` for appending, creating it if missing.
+    ///
+    /// A record left incomplete by a crash is removed, along with
+    /// anything written after it.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Changelog, Error> {
+        let path = path.as_ref();
+        let io_error = |e: io::Error| {
+            Error::new_with_kind(ErrorKind::IoError, format!("cannot open changelog: {}", e))
+                .with_path(path)
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).map_err(io_error)?;
+        let (records, len) = parse_records(&bytes);
+        let last_sequence = records.last().map(|r| r.0);
+        if len < bytes.len() {
+            file.set_len(len as u64).and_then(|_| file.sync_data()).map_err(io_error)?;
+        }
+
+        Ok(Changelog {
+            path: path.to_path_buf(),
+            state: Mutex::new(LogState {
+                file,
+                len: len as u64,
+                pending: None,
+            }),
+            last_sequence,
+        })
+    }
+
+    /// Read all records of the changelog at `path`.
+    ///
+    /// Reading stops at an incomplete record, so a log that is still being
+    /// written to yields the records completely written so far.
+    pub fn read<K: Key>(path: impl AsRef<Path>) -> Result<Vec<(u64, Writebatch<K>)>, Error> {
+        let path = path.as_ref();
+        let mut bytes = vec![];
+        File::open(path)
+            .and_then(|mut f| f.read_to_end(&mut bytes))
+            .map_err(|e| {
+                Error::new_with_kind(ErrorKind::IoError, format!("cannot read changelog: {}", e))
+                    .with_path(path)
+            })?;
+
+        parse_records(&bytes)
+            .0
+            .into_iter()
+            .map(|(sequence, batch)| {
+                Writebatch::from_bytes(batch)
+                    .map(|batch| (sequence, batch))
+                    .map_err(|e| e.with_path(path))
+            })
+            .collect()
+    }
+
+    fn io_error(&self, message: &str, e: io::Error) -> Error {
+        Error::new_with_kind(ErrorKind::IoError, format!("{}: {}", message, e)).with_path(&self.path)
+    }
+}
+
+// The sequence numbers and batches of the intact records at the start of
+// `bytes`, along with their total length.
+fn parse_records(bytes: &[u8]) -> (Vec<(u64, &[u8])>, usize) {
+    let mut records = vec![];
+    let mut len = 0;
+    while let Some((sequence, batch)) = parse_record(&bytes[len..]) {
+        records.push((sequence, batch));
+        len += RECORD_HEADER_SIZE + batch.len();
+    }
+    (records, len)
+}
+
+// The sequence number and batch of the record starting `input`, if it is
+// complete and matches its checksum.
+fn parse_record(input: &[u8]) -> Option<(u64, &[u8])> {
+    if input.len() < RECORD_HEADER_SIZE {
+        return None;
+    }
+    let mut sequence = [0; 8];
+    sequence.copy_from_slice(&input[..8]);
+    let mut len = [0; 4];
+    len.copy_from_slice(&input[8..12]);
+    let mut checksum = [0; 4];
+    checksum.copy_from_slice(&input[12..RECORD_HEADER_SIZE]);
+
+    let len = u32::from_le_bytes(len) as usize;
+    if input.len() - RECORD_HEADER_SIZE < len {
+        return None;
+    }
+    let batch = &input[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len];
+    if record_checksum(&input[..12], batch) != u32::from_le_bytes(checksum) {
+        return None;
+    }
+    Some((u64::from_le_bytes(sequence), batch))
+}
+
+fn record_checksum(header: &[u8], batch: &[u8]) -> u32 {
+    !crc32c_update(crc32c_update(!0, header), batch)
+}
+
+// CRC-32C (Castagnoli), as used by leveldb's own log files
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f6_3b78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32c_update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, &b| CRC32C_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+impl LogState {
+    // drop the pending record, if any
+    fn truncate(&mut self) -> io::Result<()> {
+        self.pending = None;
+        self.file.set_len(self.len)?;
+        self.file.sync_data()
+    }
+}
+
+impl<K: Key> ChangeListener<K> for Changelog {
+    fn prepare(&self, sequence: u64, batch: &Writebatch<K>) -> Result<(), Error> {
+        let bytes = batch.to_bytes();
+        if bytes.len() > u32::MAX as usize {
+            return Err(Error::new_with_kind(ErrorKind::InvalidArgument,
+                                            "batch too large for the changelog".to_string())
+                .with_path(&self.path));
+        }
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + bytes.len());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        let checksum = record_checksum(&record, &bytes);
+        record.extend_from_slice(&checksum.to_le_bytes());
+        record.extend_from_slice(&bytes);
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.pending = Some((sequence, record.len() as u64));
+        let res = state.file.write_all(&record).and_then(|_| state.file.sync_data());
+        if let Err(e) = res {
+            // don't leave a partial record behind
+            let _ = state.truncate();
+            return Err(self.io_error("cannot write changelog record", e));
+        }
+        Ok(())
+    }
+
+    fn committed(&self, sequence: u64, _batch: &Writebatch<K>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((pending, len)) = state.pending {
+            if pending == sequence {
+                state.pending = None;
+                state.len += len;
+            }
+        }
+    }
+
+    fn aborted(&self, sequence: u64, _batch: &Writebatch<K>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.pending.map(|(pending, _)| pending) == Some(sequence) {
+            if let Err(e) = state.truncate() {
+                error!(target: "leveldb", "cannot remove aborted changelog record {}: {}", sequence, e);
+            }
+        }
+    }
+
+    fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+}
@@ -4,45 +4,81 @@
 //! Comparators allow to override this comparison.
 //! The ordering of keys introduced by the compartor influences iteration order.
 //! Databases written with one Comparator cannot be opened with another.
+//! Attempting to do so fails with `ErrorKind::ComparatorMismatch`.
+//!
+//! `Comparator::compare` works on decoded keys, so every comparison leveldb
+//! performs decodes both keys first. Orderings that can be expressed on the
+//! encoded keys should implement `ByteComparator` instead and be used
+//! through `BytesComparator`, which compares the raw bytes directly.
 use leveldb_sys::*;
 use libc::{size_t, c_void, c_char};
+use std::ffi::{CStr, CString};
 use std::slice;
 use std::cmp::Ordering;
 use database::key::Key;
 use database::key::from_u8;
 use std::marker::PhantomData;
+use database::panic::{abort_on_panic, catch_destructor};
 
 /// A comparator has two important functions:
 ///
 /// * the name function returns a fixed name to detect errors when
 ///   opening databases with a different name
 /// * The comparison implementation
+///
+/// # Panics
+///
+/// Comparators must not panic. leveldb cannot continue without an ordering,
+/// so a comparator panicking while called by leveldb, e.g. during a write or
+/// a compaction, aborts the process. Panics in comparisons made from Rust,
+/// e.g. to check iterator bounds, unwind to the caller.
 pub trait Comparator {
     /// The type that the comparator compares.
     type K: Key;
 
     /// Return the name of the Comparator
-    fn name(&self) -> *const c_char;
+    ///
+    /// The name is persisted with the database and checked when opening it.
+    fn name(&self) -> &CStr;
     /// compare two keys. This must implement a total ordering.
     fn compare(&self, a: &Self::K, b: &Self::K) -> Ordering;
+    /// compare two encoded keys.
+    ///
+    /// The default implementation decodes both keys and calls `compare`.
+    fn compare_bytes(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let a_key = from_u8::<Self::K>(a);
+        let b_key = from_u8::<Self::K>(b);
+        self.compare(&a_key, &b_key)
+    }
     /// whether the comparator is the `DefaultComparator`
     fn null() -> bool {
         false
     }
+    /// whether the comparator orders encoded keys exactly like leveldb's
+    /// default comparator, i.e. lexicographically by their bytes.
+    ///
+    /// `Keyspace` requires such an ordering.
+    fn bytewise() -> bool {
+        false
+    }
 }
 
 /// OrdComparator is a comparator comparing Keys that implement `Ord`
 pub struct OrdComparator<K: Key + Ord> {
-    name: String,
+    name: CString,
     marker: PhantomData<K>,
 }
 
 impl<K: Key + Ord> OrdComparator<K> {
     /// Create a new OrdComparator
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a NUL byte.
     pub fn new(name: &str) -> OrdComparator<K> {
         OrdComparator {
             marker: PhantomData,
-            name: name.to_string(),
+            name: CString::new(name).expect("comparator name must not contain NUL bytes"),
         }
     }
 }
@@ -51,11 +87,18 @@ impl<K: Key + Ord> OrdComparator<K> {
 #[derive(Copy,Clone)]
 pub struct DefaultComparator;
 
+/// # Safety
+///
+/// All callbacks expect `state` to be the pointer created by `create_comparator`.
+///
+/// leveldb persists the comparator's name and relies on its ordering for
+/// all data it writes, so a comparator panicking in a callback aborts the
+/// process.
 unsafe trait InternalComparator : Comparator where Self: Sized {
 
     extern "C" fn name(state: *mut c_void) -> *const c_char {
         let x = unsafe { &*(state as *mut Self) };
-        x.name()
+        abort_on_panic("comparator", || x.name().as_ptr())
     }
 
     extern "C" fn compare(state: *mut c_void,
@@ -65,12 +108,11 @@ unsafe trait InternalComparator : Comparator where Self: Sized {
                           b_len: size_t)
                           -> i32 {
         unsafe {
-            let a_slice = slice::from_raw_parts::<u8>(a as *const u8, a_len as usize);
-            let b_slice = slice::from_raw_parts::<u8>(b as *const u8, b_len as usize);
+            let a_slice = slice::from_raw_parts::<u8>(a as *const u8, a_len);
+            let b_slice = slice::from_raw_parts::<u8>(b as *const u8, b_len);
             let x = &*(state as *mut Self);
-            let a_key = from_u8::<<Self as Comparator>::K>(a_slice);
-            let b_key = from_u8::<<Self as Comparator>::K>(b_slice);
-            match x.compare(&a_key, &b_key) {
+            let order = abort_on_panic("comparator", || x.compare_bytes(a_slice, b_slice));
+            match order {
                 Ordering::Less => -1,
                 Ordering::Equal => 0,
                 Ordering::Greater => 1,
@@ -78,30 +120,49 @@ unsafe trait InternalComparator : Comparator where Self: Sized {
         }
     }
 
+    // compares keys from Rust, letting panics unwind to the caller
+    unsafe fn compare_keys(state: *mut c_void, a: &[u8], b: &[u8]) -> Ordering {
+        let x = &*(state as *mut Self);
+        x.compare_bytes(a, b)
+    }
+
     extern "C" fn destructor(state: *mut c_void) {
-        let _x: Box<Self> = unsafe { Box::from_raw(state as *mut Self) };
-         // let the Box fall out of scope and run the T's destructor
+        let x: Box<Self> = unsafe { Box::from_raw(state as *mut Self) };
+        // let the Box fall out of scope and run the T's destructor
+        catch_destructor(|| drop(x));
     }
 }
 
 unsafe impl<C: Comparator> InternalComparator for C {}
 
-#[allow(missing_docs)]
+#[allow(missing_docs, clippy::boxed_local)]
 pub fn create_comparator<T: Comparator>(x: Box<T>) -> *mut leveldb_comparator_t {
-    unsafe {
-        leveldb_comparator_create(Box::into_raw(x) as *mut c_void,
+    create_comparator_with_state(*x).0
+}
+
+/// Create a comparator, returning it along with its state pointer.
+pub(crate) fn create_comparator_with_state<T: Comparator>(x: T) -> (*mut leveldb_comparator_t, *mut c_void) {
+    let state = Box::into_raw(Box::new(x)) as *mut c_void;
+    let ptr = unsafe {
+        leveldb_comparator_create(state,
                                   <T as InternalComparator>::destructor,
                                   <T as InternalComparator>::compare,
                                   <T as InternalComparator>::name)
-    }
+    };
+    (ptr, state)
+}
+
+/// Compares keys from Rust in the order of comparators of type `T`, given
+/// their state pointer.
+pub(crate) fn compare_fn<T: Comparator>() -> unsafe fn(*mut c_void, &[u8], &[u8]) -> Ordering {
+    <T as InternalComparator>::compare_keys
 }
 
 impl<K: Key + Ord> Comparator for OrdComparator<K> {
   type K = K;
 
-    fn name(&self) -> *const c_char {
-        let slice: &str = self.name.as_ref();
-        slice.as_ptr() as *const c_char
+    fn name(&self) -> &CStr {
+        &self.name
     }
 
     fn compare(&self, a: &K, b: &K) -> Ordering {
@@ -112,8 +173,8 @@ impl<K: Key + Ord> Comparator for OrdComparator<K> {
 impl Comparator for DefaultComparator {
   type K = i32;
 
-    fn name(&self) -> *const c_char {
-        "default_comparator".as_ptr() as *const c_char
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"default_comparator\0").unwrap()
     }
 
     fn compare(&self, _a: &i32, _b: &i32) -> Ordering {
@@ -124,3 +185,131 @@ impl Comparator for DefaultComparator {
         true
     }
 }
+
+/// A comparator working on encoded keys.
+///
+/// Use it with `Database::open_with_comparator` through `BytesComparator`.
+pub trait ByteComparator {
+    /// Return the name of the Comparator
+    fn name(&self) -> &CStr;
+    /// compare two encoded keys. This must implement a total ordering.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering;
+    /// whether the ordering is lexicographic by bytes, see
+    /// `Comparator::bytewise`.
+    fn bytewise() -> bool where Self: Sized {
+        false
+    }
+}
+
+/// BytesComparator uses a `ByteComparator` for keys of type `K`
+pub struct BytesComparator<C: ByteComparator, K: Key> {
+    comparator: C,
+    marker: PhantomData<K>,
+}
+
+impl<C: ByteComparator, K: Key> BytesComparator<C, K> {
+    /// Create a new BytesComparator
+    pub fn new(comparator: C) -> BytesComparator<C, K> {
+        BytesComparator {
+            comparator,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C: ByteComparator, K: Key> Comparator for BytesComparator<C, K> {
+    type K = K;
+
+    fn name(&self) -> &CStr {
+        self.comparator.name()
+    }
+
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.as_slice(|a| b.as_slice(|b| self.comparator.compare(a, b)))
+    }
+
+    fn compare_bytes(&self, a: &[u8], b: &[u8]) -> Ordering {
+        self.comparator.compare(a, b)
+    }
+
+    fn bytewise() -> bool {
+        C::bytewise()
+    }
+}
+
+/// Orders keys lexicographically by their bytes, like leveldb's default
+/// comparator.
+///
+/// It shares its name with leveldb's default comparator, so databases
+/// created without a comparator can be opened with it and vice versa.
+#[derive(Copy,Clone,Debug,Default)]
+pub struct Bytewise;
+
+impl ByteComparator for Bytewise {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"leveldb.BytewiseComparator\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+
+    fn bytewise() -> bool {
+        true
+    }
+}
+
+/// Orders keys lexicographically by their bytes, in reverse.
+#[derive(Copy,Clone,Debug,Default)]
+pub struct ReverseBytewise;
+
+impl ByteComparator for ReverseBytewise {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"leveldb.rs.ReverseBytewiseComparator\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+/// Orders keys as unsigned big-endian integers of any length.
+///
+/// Keys of the same value are ordered by their length, so `[1]` sorts
+/// before `[0, 1]` and distinct keys never compare equal.
+#[derive(Copy,Clone,Debug,Default)]
+pub struct BigEndianUint;
+
+impl ByteComparator for BigEndianUint {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"leveldb.rs.BigEndianUintComparator\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let a_value = strip_leading_zeros(a);
+        let b_value = strip_leading_zeros(b);
+        a_value.len().cmp(&b_value.len())
+            .then_with(|| a_value.cmp(b_value))
+            .then_with(|| a.len().cmp(&b.len()))
+    }
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Orders keys by their length first, and keys of the same length
+/// lexicographically by their bytes.
+#[derive(Copy,Clone,Debug,Default)]
+pub struct LengthThenBytes;
+
+impl ByteComparator for LengthThenBytes {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"leveldb.rs.LengthThenBytesComparator\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    }
+}
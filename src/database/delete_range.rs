@@ -0,0 +1,87 @@
+//! Deletion of key ranges
+//!
+//! leveldb has no native range deletion. `DeleteRange` removes all keys
+//! in a range by iterating over them and deleting them through a
+//! `Writebatch`.
+use super::Database;
+use super::key::Key;
+use super::batch::{Batch, Writebatch};
+use super::compaction::Compaction;
+use super::error::Error;
+use super::iterator::{Iterable, LevelDBIterator};
+use super::options::{ReadOptions, WriteOptions};
+
+/// Options for a range deletion.
+#[derive(Copy,Clone)]
+pub struct DeleteRangeOptions {
+    /// The write options used for every batch written.
+    ///
+    /// default: `WriteOptions::new()`
+    pub write: WriteOptions,
+    /// Write the deletions in batches of at most this many keys.
+    ///
+    /// Each batch is applied atomically, but the range as a whole is not.
+    /// Chunking keeps memory usage bounded when deleting huge ranges.
+    ///
+    /// default: None (a single batch)
+    pub chunk_size: Option<usize>,
+    /// Compact the range after deleting it, to reclaim disk space right away.
+    ///
+    /// default: false
+    pub compact: bool,
+}
+
+impl DeleteRangeOptions {
+    /// Create a new `DeleteRangeOptions` struct with default settings.
+    pub fn new() -> DeleteRangeOptions {
+        DeleteRangeOptions {
+            write: WriteOptions::new(),
+            chunk_size: None,
+            compact: false,
+        }
+    }
+}
+
+impl Default for DeleteRangeOptions {
+    fn default() -> DeleteRangeOptions {
+        DeleteRangeOptions::new()
+    }
+}
+
+/// Deletion of all keys in a range
+pub trait DeleteRange<K: Key> {
+    /// Delete all keys from `start` (inclusive) to `end` (exclusive).
+    ///
+    /// Returns the number of deleted keys.
+    fn delete_range(&self, options: DeleteRangeOptions, start: &K, end: &K) -> Result<usize, Error>;
+}
+
+impl<K: Key> DeleteRange<K> for Database<K> {
+    fn delete_range(&self, options: DeleteRangeOptions, start: &K, end: &K) -> Result<usize, Error> {
+        let mut batch = Writebatch::new();
+        let mut pending = 0;
+        let mut deleted = 0;
+        {
+            let mut keys = self.keys_iter(ReadOptions::new()).from(start).to_exclusive(end);
+            for key in &mut keys {
+                batch.delete(key);
+                pending += 1;
+                if Some(pending) == options.chunk_size {
+                    self.write(options.write, &batch)?;
+                    batch.clear();
+                    deleted += pending;
+                    pending = 0;
+                }
+            }
+            keys.status()?;
+        }
+        if pending > 0 {
+            self.write(options.write, &batch)?;
+            deleted += pending;
+        }
+        if options.compact {
+            self.compact(start, end);
+        }
+        Ok(deleted)
+    }
+}
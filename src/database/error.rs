@@ -3,18 +3,92 @@
 use libc::c_void;
 use leveldb_sys::leveldb_free;
 use std;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-/// A leveldb error, just containing the error string
-/// provided by leveldb.
-#[derive(Debug)]
+/// The kind of a leveldb error, derived from leveldb's status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A requested file or entry does not exist
+    NotFound,
+    /// Stored data is corrupted
+    Corruption,
+    /// The operation is not supported
+    NotSupported,
+    /// An argument is invalid, e.g. opening a missing database
+    /// without `create_if_missing`
+    InvalidArgument,
+    /// An I/O operation failed
+    IoError,
+    /// The database is locked, e.g. because it is already open
+    LockHeld,
+    /// The database was created with another comparator than the one
+    /// it is opened with
+    ComparatorMismatch,
+    /// A callback implemented in Rust, e.g. a filter policy or logger,
+    /// panicked
+    Panicked,
+    /// Any other error
+    Other,
+}
+
+/// The operation that produced an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Opening a database
+    Open,
+    /// Reading a value
+    Get,
+    /// Writing a value
+    Put,
+    /// Deleting a value
+    Delete,
+    /// Writing a batch
+    Write,
+    /// Iterating over the database
+    Iterate,
+    /// Repairing a database
+    Repair,
+    /// Destroying a database
+    Destroy,
+    /// Taking a backup
+    Backup,
+}
+
+/// A leveldb error, containing the error string
+/// provided by leveldb and where it occurred.
+#[derive(Debug, Clone)]
 pub struct Error {
+    kind: ErrorKind,
     message: String,
+    operation: Option<Operation>,
+    path: Option<PathBuf>,
 }
 
 impl Error {
     /// create a new Error, using the String provided
+    ///
+    /// The kind is derived from the status prefix leveldb puts in
+    /// front of its messages, e.g. `Corruption: `.
     pub fn new(message: String) -> Error {
-        Error { message: message }
+        if let Some((stored, supplied)) = parse_comparator_mismatch(&message) {
+            let message = format!("comparator mismatch: database was created with comparator \
+                                   `{}`, but opened with comparator `{}`",
+                                  stored,
+                                  supplied);
+            return Error::new_with_kind(ErrorKind::ComparatorMismatch, message);
+        }
+        Error::new_with_kind(parse_kind(&message), message)
+    }
+
+    /// create a new Error of the given kind.
+    pub fn new_with_kind(kind: ErrorKind, message: String) -> Error {
+        Error {
+            kind,
+            message,
+            operation: None,
+            path: None,
+        }
     }
 
     /// create an error from a c-string buffer.
@@ -22,18 +96,99 @@ impl Error {
     /// This method is `unsafe` because the pointer must be valid and point to heap.
     /// The pointer will be passed to `free`!
     pub unsafe fn new_from_i8(message: *const i8) -> Error {
-        use std::str::from_utf8;
         use std::ffi::CStr;
 
-        let err_string = from_utf8(CStr::from_ptr(message).to_bytes()).unwrap().to_string();
+        let err_string = CStr::from_ptr(message).to_string_lossy().into_owned();
         leveldb_free(message as *mut c_void);
         Error::new(err_string)
     }
+
+    /// Record the operation that produced this error.
+    pub fn with_operation(self, operation: Operation) -> Error {
+        Error { operation: Some(operation), ..self }
+    }
+
+    /// Record the path of the database or file the error relates to.
+    pub fn with_path(self, path: &Path) -> Error {
+        Error { path: Some(path.to_path_buf()), ..self }
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The message describing this error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The operation that produced this error, if known.
+    pub fn operation(&self) -> Option<Operation> {
+        self.operation
+    }
+
+    /// The path the error relates to, if known.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+fn parse_kind(message: &str) -> ErrorKind {
+    if message.starts_with("NotFound: ") {
+        ErrorKind::NotFound
+    } else if message.starts_with("Corruption: ") {
+        ErrorKind::Corruption
+    } else if message.starts_with("Not implemented: ") {
+        ErrorKind::NotSupported
+    } else if message.starts_with("Invalid argument: ") {
+        ErrorKind::InvalidArgument
+    } else if message.starts_with("IO error: lock ") &&
+              (message.contains("already held") || message.contains("temporarily unavailable")) {
+        ErrorKind::LockHeld
+    } else if message.starts_with("IO error: ") {
+        ErrorKind::IoError
+    } else {
+        ErrorKind::Other
+    }
+}
+
+// leveldb reports "<stored> does not match existing comparator : <supplied>"
+fn parse_comparator_mismatch(message: &str) -> Option<(&str, &str)> {
+    let message = message.trim_start_matches("Invalid argument: ");
+    let mut parts = message.splitn(2, " does not match existing comparator : ");
+    match (parts.next(), parts.next()) {
+        (Some(stored), Some(supplied)) => Some((stored, supplied)),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Operation::Open => "open",
+            Operation::Get => "get",
+            Operation::Put => "put",
+            Operation::Delete => "delete",
+            Operation::Write => "write",
+            Operation::Iterate => "iterate",
+            Operation::Repair => "repair",
+            Operation::Destroy => "destroy",
+            Operation::Backup => "backup",
+        };
+        f.write_str(name)
+    }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "LevelDB error: {}", self.message)
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LevelDB error: {}", self.message)?;
+        match (self.operation, &self.path) {
+            (Some(op), Some(path)) => write!(f, " ({} {})", op, path.display()),
+            (Some(op), None) => write!(f, " ({})", op),
+            (None, Some(path)) => write!(f, " ({})", path.display()),
+            (None, None) => Ok(()),
+        }
     }
 }
 
@@ -0,0 +1,198 @@
+//! Structs and traits to work with leveldb filter policies.
+//!
+//! A filter policy lets leveldb skip reading table blocks for keys that
+//! are not contained in them, which greatly reduces disk reads for lookups
+//! of missing keys.
+//!
+//! Besides the built-in bloom filter, custom policies can be implemented
+//! in Rust through the `FilterPolicy` trait.
+use leveldb_sys::{leveldb_filterpolicy_t, leveldb_filterpolicy_create_bloom,
+                  leveldb_filterpolicy_destroy};
+use libc::{c_int, c_uchar, c_char, c_void, size_t, malloc};
+use std::ffi::CStr;
+use std::slice;
+use std::ptr;
+use std::sync::Arc;
+use database::error::Error;
+use database::panic::{Guarded, PanicSlot, catch_destructor};
+
+// not exposed by leveldb-sys
+extern "C" {
+    fn leveldb_filterpolicy_create(state: *mut c_void,
+                                   destructor: extern "C" fn(*mut c_void),
+                                   create_filter: extern "C" fn(*mut c_void,
+                                                                *const *const c_char,
+                                                                *const size_t,
+                                                                c_int,
+                                                                *mut size_t)
+                                                                -> *mut c_char,
+                                   key_may_match: extern "C" fn(*mut c_void,
+                                                                *const c_char,
+                                                                size_t,
+                                                                *const c_char,
+                                                                size_t)
+                                                                -> c_uchar,
+                                   name: extern "C" fn(*mut c_void) -> *const c_char)
+                                   -> *mut leveldb_filterpolicy_t;
+}
+
+/// A filter policy implemented in Rust.
+///
+/// In contrast to `Comparator`, filter policies work on the raw key bytes,
+/// so that filters can look at parts of a key, e.g. a prefix.
+///
+/// The name is persisted along with the filters. Filters written under
+/// another name are ignored, so the name must change whenever the filter
+/// encoding changes.
+///
+/// leveldb builds filters on its background compaction thread and checks
+/// them from concurrent readers, hence the `Send + Sync` bound.
+pub trait FilterPolicy: Send + Sync {
+    /// Return the name of the filter policy
+    fn name(&self) -> &CStr;
+    /// Build a filter summarizing the given keys.
+    fn create_filter(&self, keys: &[&[u8]]) -> Vec<u8>;
+    /// Return whether the key may be contained in the set summarized
+    /// by `filter`. Returning `true` for keys not in the set is allowed,
+    /// returning `false` for keys in the set is not.
+    fn key_may_match(&self, key: &[u8], filter: &[u8]) -> bool;
+}
+
+#[allow(missing_docs)]
+struct RawFilterPolicy {
+    ptr: *mut leveldb_filterpolicy_t,
+}
+
+impl Drop for RawFilterPolicy {
+    fn drop(&mut self) {
+        unsafe {
+            leveldb_filterpolicy_destroy(self.ptr);
+        }
+    }
+}
+
+/// Represents a leveldb filter policy
+pub struct Filter {
+    raw: RawFilterPolicy,
+    // receives panics of a custom policy's callbacks
+    panic: Option<Arc<PanicSlot>>,
+}
+
+impl Filter {
+    /// Create a filter from a custom `FilterPolicy`.
+    pub fn new<T: FilterPolicy>(policy: T) -> Filter {
+        let (policy, panic) = create_guarded_filter_policy(policy);
+        Filter {
+            raw: RawFilterPolicy { ptr: policy },
+            panic: Some(panic),
+        }
+    }
+
+    /// Create a bloom filter policy using the given number of bits per key.
+    ///
+    /// 10 bits per key yield a false positive rate of about 1%.
+    pub fn bloom(bits_per_key: i32) -> Filter {
+        let policy = unsafe { leveldb_filterpolicy_create_bloom(bits_per_key as c_int) };
+        Filter {
+            raw: RawFilterPolicy { ptr: policy },
+            panic: None,
+        }
+    }
+
+    /// Report a panic caught in a callback of the policy, if any.
+    pub(crate) fn check_panic(&self) -> Result<(), Error> {
+        match self.panic {
+            Some(ref panic) => panic.check(),
+            None => Ok(()),
+        }
+    }
+
+    #[allow(missing_docs)]
+    pub fn raw_ptr(&self) -> *mut leveldb_filterpolicy_t {
+        self.raw.ptr
+    }
+}
+
+// returned to leveldb if `FilterPolicy::name` panics
+const PANICKED_NAME: &[u8] = b"leveldb.rs.panicked\0";
+
+/// # Safety
+///
+/// All callbacks expect `state` to be the pointer created by `create_filter_policy`.
+unsafe trait InternalFilterPolicy : FilterPolicy where Self: Sized {
+
+    extern "C" fn name(state: *mut c_void) -> *const c_char {
+        let x = unsafe { &*(state as *mut Guarded<Self>) };
+        x.panic.catch(|| x.inner.name().as_ptr(), || PANICKED_NAME.as_ptr() as *const c_char)
+    }
+
+    extern "C" fn create_filter(state: *mut c_void,
+                                keys: *const *const c_char,
+                                key_lens: *const size_t,
+                                num_keys: c_int,
+                                filter_len: *mut size_t)
+                                -> *mut c_char {
+        unsafe {
+            let x = &*(state as *mut Guarded<Self>);
+            let num_keys = num_keys as usize;
+            let keys = slice::from_raw_parts(keys, num_keys);
+            let key_lens = slice::from_raw_parts(key_lens, num_keys);
+            let key_slices: Vec<&[u8]> = keys.iter()
+                .zip(key_lens)
+                .map(|(k, l)| slice::from_raw_parts(*k as *const u8, *l))
+                .collect();
+            // an empty filter matches every key, see `key_may_match`
+            let filter = x.panic.catch(|| x.inner.create_filter(&key_slices), Vec::new);
+
+            // leveldb releases the filter using `free`
+            let res = malloc(filter.len().max(1)) as *mut c_char;
+            ptr::copy_nonoverlapping(filter.as_ptr() as *const c_char, res, filter.len());
+            *filter_len = filter.len() as size_t;
+            res
+        }
+    }
+
+    extern "C" fn key_may_match(state: *mut c_void,
+                                key: *const c_char,
+                                key_len: size_t,
+                                filter: *const c_char,
+                                filter_len: size_t)
+                                -> c_uchar {
+        unsafe {
+            let x = &*(state as *mut Guarded<Self>);
+            let key_slice = slice::from_raw_parts::<u8>(key as *const u8, key_len);
+            let filter_slice = slice::from_raw_parts::<u8>(filter as *const u8, filter_len);
+            // written in place of a filter whose creation panicked
+            if filter_slice.is_empty() {
+                return 1;
+            }
+            x.panic.catch(|| x.inner.key_may_match(key_slice, filter_slice), || true) as c_uchar
+        }
+    }
+
+    extern "C" fn destructor(state: *mut c_void) {
+        let x: Box<Guarded<Self>> = unsafe { Box::from_raw(state as *mut Guarded<Self>) };
+        // let the Box fall out of scope and run the T's destructor
+        catch_destructor(|| drop(x));
+    }
+}
+
+unsafe impl<F: FilterPolicy> InternalFilterPolicy for F {}
+
+#[allow(missing_docs, clippy::boxed_local)]
+pub fn create_filter_policy<T: FilterPolicy>(x: Box<T>) -> *mut leveldb_filterpolicy_t {
+    create_guarded_filter_policy(*x).0
+}
+
+fn create_guarded_filter_policy<T: FilterPolicy>(x: T) -> (*mut leveldb_filterpolicy_t, Arc<PanicSlot>) {
+    let guarded = Guarded::new(x);
+    let panic = guarded.panic.clone();
+    let ptr = unsafe {
+        leveldb_filterpolicy_create(Box::into_raw(Box::new(guarded)) as *mut c_void,
+                                    <T as InternalFilterPolicy>::destructor,
+                                    <T as InternalFilterPolicy>::create_filter,
+                                    <T as InternalFilterPolicy>::key_may_match,
+                                    <T as InternalFilterPolicy>::name)
+    };
+    (ptr, panic)
+}
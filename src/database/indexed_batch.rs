@@ -0,0 +1,149 @@
+//! Write batches that can be read from
+//!
+//! A `Writebatch` is write-only. `WritebatchWithIndex` additionally keeps
+//! its pending writes sorted in memory, ordered like the database, so they
+//! can be read back and iterated over merged with the database contents
+//! before the batch is written.
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter;
+
+use super::Database;
+use super::key::{Key, from_u8};
+use super::batch::Writebatch;
+use super::error::Error;
+use super::iterator::{Iterable, Iterator, LevelDBIterator};
+use super::options::ReadOptions;
+
+/// A write batch indexed by key, for reading its own writes.
+pub struct WritebatchWithIndex<'a, K: Key + 'a> {
+    database: &'a Database<K>,
+    batch: Writebatch<K>,
+    // pending writes by raw key, sorted by the database's comparator;
+    // `None` marks a deletion
+    index: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// An iterator over the database with the pending writes of a batch applied.
+pub struct IndexedIterator<'b, K: Key + 'b> {
+    database: &'b Database<K>,
+    inner: Iterator<'b, K>,
+    index: &'b [(Vec<u8>, Option<Vec<u8>>)],
+    // the next entry of `inner`, if already read
+    peeked: Option<(Vec<u8>, Vec<u8>)>,
+    inner_done: bool,
+}
+
+impl<'a, K: Key> WritebatchWithIndex<'a, K> {
+    /// Create a new batch on top of `database`.
+    pub fn new(database: &'a Database<K>) -> WritebatchWithIndex<'a, K> {
+        WritebatchWithIndex {
+            database,
+            batch: Writebatch::new(),
+            index: vec![],
+        }
+    }
+
+    /// Clear the writebatch
+    pub fn clear(&mut self) {
+        self.batch.clear();
+        self.index.clear();
+    }
+
+    /// Batch a put operation
+    pub fn put(&mut self, key: K, value: &[u8]) {
+        let key = key.as_slice(|k| k.to_vec());
+        self.batch.put_raw(&key, value);
+        self.insert(key, Some(value.to_vec()));
+    }
+
+    /// Batch a delete operation
+    pub fn delete(&mut self, key: K) {
+        let key = key.as_slice(|k| k.to_vec());
+        self.batch.delete_raw(&key);
+        self.insert(key, None);
+    }
+
+    /// get a value, taking pending writes of this batch into account.
+    pub fn get<BK: Borrow<K>>(&self, options: ReadOptions<'a, K>, key: BK) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        match self.find(&key) {
+            Ok(pos) => Ok(self.index[pos].1.clone()),
+            Err(_) => {
+                self.database
+                    .get_bytes_raw(&options, &key)
+                    .map(|val| val.map(Into::into))
+            }
+        }
+    }
+
+    /// Iterate over (Key, Value) pairs of the database with the pending
+    /// writes of this batch applied.
+    pub fn iter<'b>(&'b self, options: ReadOptions<'b, K>) -> IndexedIterator<'b, K> {
+        IndexedIterator {
+            database: self.database,
+            inner: self.database.iter(options),
+            index: &self.index,
+            peeked: None,
+            inner_done: false,
+        }
+    }
+
+    /// The underlying batch, to be written using `Batch::write`.
+    pub fn writebatch(&self) -> &Writebatch<K> {
+        &self.batch
+    }
+
+    fn find(&self, key: &[u8]) -> Result<usize, usize> {
+        self.index.binary_search_by(|probe| self.database.compare(&probe.0, key))
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+        match self.find(&key) {
+            Ok(pos) => self.index[pos].1 = value,
+            Err(pos) => self.index.insert(pos, (key, value)),
+        }
+    }
+}
+
+impl<'b, K: Key> IndexedIterator<'b, K> {
+    /// Return the error that stopped the iteration, if any.
+    pub fn status(&self) -> Result<(), Error> {
+        self.inner.status()
+    }
+
+    fn peek_inner(&mut self) {
+        if self.peeked.is_none() && !self.inner_done {
+            self.peeked = self.inner.next_bytes().map(|(k, v)| (k.to_vec(), v.to_vec()));
+            self.inner_done = self.peeked.is_none();
+        }
+    }
+}
+
+impl<'b, K: Key> iter::Iterator for IndexedIterator<'b, K> {
+    type Item = (K, Vec<u8>);
+
+    fn next(&mut self) -> Option<(K, Vec<u8>)> {
+        loop {
+            self.peek_inner();
+            let order = match (&self.peeked, self.index.first()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((key, _)), Some((pending, _))) => self.database.compare(key, pending),
+            };
+            if order == Ordering::Less {
+                return self.peeked.take().map(|(k, v)| (from_u8(&k), v));
+            }
+            if order == Ordering::Equal {
+                // the pending write shadows the stored entry
+                self.peeked = None;
+            }
+            let ((key, value), rest) = self.index.split_first().unwrap();
+            self.index = rest;
+            if let Some(value) = value {
+                return Some((from_u8(key), value.clone()));
+            }
+        }
+    }
+}
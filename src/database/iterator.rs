@@ -2,17 +2,36 @@
 //!
 //! Iteration is one of the most important parts of leveldb. This module provides
 //! Iterators to iterate over key, values and pairs of both.
+//!
+//! All iterators can be walked from both ends, either through `DoubleEndedIterator`
+//! or by calling `advance` and `prev` directly. Bounds set through `from` and `to`
+//! (or `to_exclusive`) are respected in both directions and are compared using
+//! the ordering of the database, including custom comparators.
+//!
+//! `seek`, `seek_to_first` and `seek_to_last` reposition both ends, also of an
+//! exhausted iterator: the next `advance` or `prev` returns the entry sought to
+//! and iteration continues from there.
+//!
+//! Besides decoded keys and owned values, the current entry can be accessed
+//! through `key_bytes` and `value_bytes` (or `next_bytes`), which borrow
+//! leveldb's buffers until the iterator moves and avoid any allocation.
+//!
+//! An iterator also stops when leveldb hits an error, e.g. due to corruption.
+//! Call `status` after iteration to tell a complete scan from a partial one.
 use leveldb_sys::{leveldb_iterator_t, leveldb_iter_seek_to_first, leveldb_iter_destroy,
                   leveldb_iter_seek_to_last, leveldb_create_iterator, leveldb_iter_valid,
-                  leveldb_iter_next, leveldb_iter_key, leveldb_iter_value,
-                  leveldb_readoptions_destroy, leveldb_iter_seek};
+                  leveldb_iter_next, leveldb_iter_prev, leveldb_iter_key, leveldb_iter_value,
+                  leveldb_readoptions_destroy, leveldb_iter_seek, leveldb_iter_get_error};
 use libc::{size_t, c_char};
 use std::iter;
+use std::ptr;
+use std::cmp::Ordering;
 use super::Database;
+use super::error::{Error, Operation};
 use super::options::{ReadOptions, c_readoptions};
 use super::key::{Key, from_u8};
 use std::slice::from_raw_parts;
-use std::marker::PhantomData;
+use std::path::Path;
 
 #[allow(missing_docs)]
 struct RawIterator {
@@ -31,13 +50,31 @@ impl Drop for RawIterator {
 /// Returns key and value as a tuple.
 pub struct Iterator<'a, K: Key + 'a> {
     start: bool,
-    // Iterator accesses the Database through a leveldb_iter_t pointer
-    // but needs to hold the reference for lifetime tracking
-    #[allow(dead_code)]
-    database: PhantomData<&'a Database<K>>,
+    back_start: bool,
+    // Iterator accesses the Database through a leveldb_iter_t pointer,
+    // the reference is used to compare keys in the database's ordering
+    database: &'a Database<K>,
     iter: RawIterator,
     from: Option<&'a K>,
     to: Option<&'a K>,
+    // the raw bounds derived from `from` and `to`
+    lower: Option<Vec<u8>>,
+    upper: Option<Vec<u8>>,
+    upper_inclusive: bool,
+    // prepended to bounds and stripped from keys when confined to a table
+    prefix: Vec<u8>,
+    // the range the iterator is confined to, bounds never reach outside of it
+    range: (Option<Vec<u8>>, Option<Vec<u8>>),
+    // the raw keys last returned from the front and the back,
+    // iteration ends once both ends meet
+    front: Option<Vec<u8>>,
+    back: Option<Vec<u8>>,
+    // whether the raw iterator was last moved forward, i.e. sits on `front`
+    forward: bool,
+    // whether the raw iterator was just sought and sits on the entry
+    // the next step in either direction returns
+    sought: bool,
+    done: bool,
 }
 
 /// An iterator over the leveldb keyspace.
@@ -63,6 +100,13 @@ pub trait Iterable<'a, K: Key + 'a> {
     fn keys_iter(&'a self, options: ReadOptions<'a, K>) -> KeyIterator<K>;
     /// Returns an Iterator iterating over Values only.
     fn value_iter(&'a self, options: ReadOptions<'a, K>) -> ValueIterator<K>;
+    /// Returns an Iterator iterating over (Key,Value) pairs whose raw key
+    /// starts with `prefix`.
+    ///
+    /// The prefix is matched against the encoded key bytes, independent
+    /// of `K`. Iteration relies on keys sharing a prefix being stored next
+    /// to each other, which holds for the default comparator.
+    fn prefix_iter(&'a self, options: ReadOptions<'a, K>, prefix: &[u8]) -> Iterator<'a, K>;
 }
 
 impl<'a, K: Key + 'a> Iterable<'a, K> for Database<K> {
@@ -77,6 +121,10 @@ impl<'a, K: Key + 'a> Iterable<'a, K> for Database<K> {
     fn value_iter(&'a self, options: ReadOptions<'a, K>) -> ValueIterator<K> {
         ValueIterator::new(self, options)
     }
+
+    fn prefix_iter(&'a self, options: ReadOptions<'a, K>, prefix: &[u8]) -> Iterator<'a, K> {
+        Iterator::with_prefix(self, options, &[], prefix)
+    }
 }
 
 #[allow(missing_docs)]
@@ -90,8 +138,12 @@ pub trait LevelDBIterator<'a, K: Key> {
     #[inline]
     fn started(&mut self);
 
+    /// Start iterating at `key`, inclusive.
     fn from(self, key: &'a K) -> Self;
+    /// Stop iterating at `key`, inclusive.
     fn to(self, key: &'a K) -> Self;
+    /// Stop iterating before `key`, exclusive.
+    fn to_exclusive(self, key: &'a K) -> Self;
 
     fn from_key(&self) -> Option<&K>;
     fn to_key(&self) -> Option<&K>;
@@ -100,52 +152,87 @@ pub trait LevelDBIterator<'a, K: Key> {
         unsafe { leveldb_iter_valid(self.raw_iterator()) != 0 }
     }
 
-    fn advance(&mut self) -> bool {
-        unsafe {
-            if !self.start() {
+    /// Move to the next entry from the front, returning whether there is one.
+    fn advance(&mut self) -> bool;
 
-                leveldb_iter_next(self.raw_iterator());
-            } else {
-                if let Some(k) = self.from_key() {
-                    self.seek(k)
-                }
-                self.started();
-            }
-        }
-        self.valid()
+    /// Move to the next entry from the back, returning whether there is one.
+    fn prev(&mut self) -> bool;
+
+    /// Return the error that stopped the iteration, if any.
+    ///
+    /// An iterator that ran into corruption or an I/O error simply becomes
+    /// invalid, so this should be checked once iteration finished.
+    fn status(&self) -> Result<(), Error> {
+        raw_status(self.raw_iterator())
     }
 
-    fn key(&self) -> K {
+    /// The key at the current position, borrowed from leveldb.
+    ///
+    /// The slice stays valid until the iterator is moved, so keys can
+    /// be inspected without allocating or decoding them.
+    fn key_bytes(&self) -> &[u8] {
         unsafe {
             let length: size_t = 0;
-            let value = leveldb_iter_key(self.raw_iterator(), &length) as *const u8;
-            from_u8(from_raw_parts(value, length as usize))
+            let key = leveldb_iter_key(self.raw_iterator(), &length) as *const u8;
+            from_raw_parts(key, length as usize)
         }
     }
 
-    fn value(&self) -> Vec<u8> {
+    /// The value at the current position, borrowed from leveldb.
+    ///
+    /// The slice stays valid until the iterator is moved.
+    fn value_bytes(&self) -> &[u8] {
         unsafe {
             let length: size_t = 0;
             let value = leveldb_iter_value(self.raw_iterator(), &length) as *const u8;
-            from_raw_parts(value, length as usize).to_vec()
+            from_raw_parts(value, length as usize)
         }
     }
 
-    fn seek_to_first(&self) {
-        unsafe { leveldb_iter_seek_to_first(self.raw_iterator()) }
+    /// Move to the next entry from the front and return its key and value
+    /// borrowed from leveldb, valid until the iterator is moved again.
+    fn next_bytes(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.advance() {
+            Some((self.key_bytes(), self.value_bytes()))
+        } else {
+            None
+        }
     }
 
-    fn seek_to_last(&self) {
-        if let Some(k) = self.to_key() {
-            self.seek(k);
+    /// Move to the next entry from the back and return its key and value
+    /// borrowed from leveldb, valid until the iterator is moved again.
+    fn next_back_bytes(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.prev() {
+            Some((self.key_bytes(), self.value_bytes()))
         } else {
-            unsafe {
+            None
+        }
+    }
+
+    fn key(&self) -> K {
+        from_u8(self.key_bytes())
+    }
+
+    fn value(&self) -> Vec<u8> {
+        self.value_bytes().to_vec()
+    }
+
+    fn seek_to_first(&mut self) {
+        unsafe { leveldb_iter_seek_to_first(self.raw_iterator()) }
+    }
+
+    fn seek_to_last(&mut self) {
+        match self.to_key().map(|k| k.as_slice(|s| s.to_vec())) {
+            Some(k) => unsafe {
+                leveldb_iter_seek(self.raw_iterator(), k.as_ptr() as *mut c_char, k.len() as size_t);
+            },
+            None => unsafe {
                 leveldb_iter_seek_to_last(self.raw_iterator());
-            }
+            },
         }
     }
 
-    fn seek(&self, key: &K) {
+    fn seek(&mut self, key: &K) {
         unsafe {
             key.as_slice(|k| {
                 leveldb_iter_seek(self.raw_iterator(),
@@ -166,18 +253,246 @@ impl<'a, K: Key> Iterator<'a, K> {
             leveldb_iter_seek_to_first(ptr);
             Iterator {
                 start: true,
+                back_start: true,
                 iter: RawIterator { ptr: ptr },
-                database: PhantomData,
+                database,
                 from: None,
                 to: None,
+                lower: None,
+                upper: None,
+                upper_inclusive: false,
+                prefix: vec![],
+                range: (None, None),
+                front: None,
+                back: None,
+                forward: true,
+                sought: false,
+                done: false,
             }
         }
     }
 
+    /// Create an iterator confined to the keys starting with `range_prefix`.
+    ///
+    /// `key_prefix` is removed from keys before decoding them and prepended
+    /// to the bounds passed to `from` and `to`. It must be a prefix of
+    /// `range_prefix`.
+    pub(crate) fn with_prefix(database: &'a Database<K>,
+                              options: ReadOptions<'a, K>,
+                              key_prefix: &[u8],
+                              range_prefix: &[u8])
+                              -> Iterator<'a, K> {
+        let mut iter = Iterator::new(database, options);
+        iter.range = (Some(range_prefix.to_vec()), prefix_successor(range_prefix));
+        iter.lower = iter.range.0.clone();
+        iter.upper = iter.range.1.clone();
+        iter.prefix = key_prefix.to_vec();
+        iter
+    }
+
     /// return the last element of the iterator
-    pub fn last(self) -> Option<(K, Vec<u8>)> {
-        self.seek_to_last();
-        Some((self.key(), self.value()))
+    pub fn last(mut self) -> Option<(K, Vec<u8>)> {
+        self.next_back()
+    }
+
+    /// The path of the database iterated over.
+    pub(crate) fn path(&self) -> &Path {
+        &self.database.path
+    }
+
+    fn raw_key(&self) -> &[u8] {
+        unsafe {
+            let length: size_t = 0;
+            let key = leveldb_iter_key(self.iter.ptr, &length) as *const u8;
+            from_raw_parts(key, length as usize)
+        }
+    }
+
+    // copy the current key into `buf`, reusing its allocation so that
+    // scans do not allocate for every key
+    fn record_key(&self, buf: Option<Vec<u8>>) -> Vec<u8> {
+        let mut buf = buf.unwrap_or_default();
+        buf.clear();
+        buf.extend_from_slice(self.raw_key());
+        buf
+    }
+
+    fn seek_raw(&self, key: &[u8]) {
+        unsafe {
+            leveldb_iter_seek(self.iter.ptr, key.as_ptr() as *mut c_char, key.len() as size_t);
+        }
+    }
+
+    fn raw_bound(&self, key: &K) -> Vec<u8> {
+        key.as_slice(|k| [&self.prefix[..], k].concat())
+    }
+
+    fn set_lower(&mut self, key: &K) {
+        let bound = self.raw_bound(key);
+        self.lower = match self.range.0 {
+            Some(ref r) if self.database.compare(r, &bound) == Ordering::Greater => Some(r.clone()),
+            _ => Some(bound),
+        };
+    }
+
+    fn set_upper(&mut self, key: &K, inclusive: bool) {
+        let bound = self.raw_bound(key);
+        match self.range.1 {
+            Some(ref r) if self.database.compare(&bound, r) != Ordering::Less => {
+                self.upper = Some(r.clone());
+                self.upper_inclusive = false;
+            }
+            _ => {
+                self.upper = Some(bound);
+                self.upper_inclusive = inclusive;
+            }
+        }
+    }
+
+    fn below_lower(&self) -> bool {
+        match self.lower {
+            Some(ref l) => self.database.compare(self.raw_key(), l) == Ordering::Less,
+            None => false,
+        }
+    }
+
+    fn above_upper(&self) -> bool {
+        match self.upper {
+            Some(ref u) => {
+                match self.database.compare(self.raw_key(), u) {
+                    Ordering::Greater => true,
+                    Ordering::Equal => !self.upper_inclusive,
+                    Ordering::Less => false,
+                }
+            }
+            None => false,
+        }
+    }
+
+    // position on the first key within the bounds
+    fn seek_lower(&self) {
+        match self.lower {
+            Some(ref l) => self.seek_raw(l),
+            None => unsafe { leveldb_iter_seek_to_first(self.iter.ptr) },
+        }
+    }
+
+    // position on the last key within the bounds
+    fn seek_upper(&self) {
+        match self.upper {
+            Some(ref u) => {
+                self.seek_raw(u);
+                if !self.valid() {
+                    unsafe { leveldb_iter_seek_to_last(self.iter.ptr) };
+                } else if self.above_upper() {
+                    unsafe { leveldb_iter_prev(self.iter.ptr) };
+                }
+            }
+            None => unsafe { leveldb_iter_seek_to_last(self.iter.ptr) },
+        }
+    }
+
+    // forget the position of both ends after the raw iterator was sought,
+    // so that iteration continues from the new position
+    fn reposition(&mut self) {
+        self.start = false;
+        self.back_start = false;
+        self.front = None;
+        self.back = None;
+        self.sought = true;
+        self.done = false;
+    }
+
+    fn step_forward(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+        if self.sought {
+            self.sought = false;
+        } else if self.start {
+            self.seek_lower();
+            self.start = false;
+        } else {
+            if !self.forward {
+                if let Some(ref k) = self.front {
+                    self.seek_raw(k);
+                }
+            }
+            unsafe { leveldb_iter_next(self.iter.ptr) };
+        }
+        self.forward = true;
+
+        if !self.valid() || self.above_upper() {
+            self.done = true;
+            return false;
+        }
+        if let Some(ref back) = self.back {
+            if self.database.compare(self.raw_key(), back) != Ordering::Less {
+                self.done = true;
+                return false;
+            }
+        }
+        let buf = self.front.take();
+        self.front = Some(self.record_key(buf));
+        true
+    }
+
+    fn step_backward(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+        if self.sought {
+            self.sought = false;
+        } else if self.back_start {
+            self.seek_upper();
+            self.back_start = false;
+        } else {
+            if self.forward {
+                if let Some(ref k) = self.back {
+                    self.seek_raw(k);
+                }
+            }
+            unsafe { leveldb_iter_prev(self.iter.ptr) };
+        }
+        self.forward = false;
+
+        if !self.valid() || self.below_lower() {
+            self.done = true;
+            return false;
+        }
+        if let Some(ref front) = self.front {
+            if self.database.compare(self.raw_key(), front) != Ordering::Greater {
+                self.done = true;
+                return false;
+            }
+        }
+        let buf = self.back.take();
+        self.back = Some(self.record_key(buf));
+        true
+    }
+}
+
+// The smallest key greater than all keys starting with `prefix`, if any.
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut succ = prefix.to_vec();
+    while let Some(last) = succ.pop() {
+        if last != 0xff {
+            succ.push(last + 1);
+            return Some(succ);
+        }
+    }
+    None
+}
+
+fn raw_status(iter: *mut leveldb_iterator_t) -> Result<(), Error> {
+    unsafe {
+        let error: *const c_char = ptr::null();
+        leveldb_iter_get_error(iter, &error);
+        if error.is_null() {
+            Ok(())
+        } else {
+            Err(Error::new_from_i8(error).with_operation(Operation::Iterate))
+        }
     }
 }
 
@@ -187,6 +502,11 @@ impl<'a, K: Key> LevelDBIterator<'a, K> for Iterator<'a,K> {
         self.iter.ptr
     }
 
+    fn status(&self) -> Result<(), Error> {
+        raw_status(self.iter.ptr).map_err(|e| e.with_path(&self.database.path))?;
+        self.database.check_panics(Operation::Iterate)
+    }
+
     #[inline]
     fn start(&self) -> bool {
         self.start
@@ -197,16 +517,73 @@ impl<'a, K: Key> LevelDBIterator<'a, K> for Iterator<'a,K> {
         self.start = false
     }
 
+    fn advance(&mut self) -> bool {
+        self.step_forward()
+    }
+
+    fn prev(&mut self) -> bool {
+        self.step_backward()
+    }
+
     fn from(mut self, key: &'a K) -> Self {
         self.from = Some(key);
+        self.set_lower(key);
         self
     }
 
     fn to(mut self, key: &'a K) -> Self {
         self.to = Some(key);
+        self.set_upper(key, true);
+        self
+    }
+
+    fn to_exclusive(mut self, key: &'a K) -> Self {
+        self.to = Some(key);
+        self.set_upper(key, false);
         self
     }
 
+    fn key_bytes(&self) -> &[u8] {
+        &self.raw_key()[self.prefix.len()..]
+    }
+
+    // Seeking ignores the bounds set through `from` and `to`, like for
+    // any other iterator, but never leaves the range of a table or prefix.
+    // The next `advance` or `prev` returns the entry sought to and
+    // continues from there.
+    fn seek_to_first(&mut self) {
+        match self.range.0 {
+            Some(ref r) => self.seek_raw(r),
+            None => unsafe { leveldb_iter_seek_to_first(self.iter.ptr) },
+        }
+        self.reposition();
+    }
+
+    fn seek_to_last(&mut self) {
+        match self.to.map(|k| self.raw_bound(k)) {
+            Some(k) => self.seek_raw(&k),
+            None => {
+                match self.range.1 {
+                    Some(ref r) => {
+                        self.seek_raw(r);
+                        if self.valid() {
+                            unsafe { leveldb_iter_prev(self.iter.ptr) };
+                        } else {
+                            unsafe { leveldb_iter_seek_to_last(self.iter.ptr) };
+                        }
+                    }
+                    None => unsafe { leveldb_iter_seek_to_last(self.iter.ptr) },
+                }
+            }
+        }
+        self.reposition();
+    }
+
+    fn seek(&mut self, key: &K) {
+        self.seek_raw(&self.raw_bound(key));
+        self.reposition();
+    }
+
     fn from_key(&self) -> Option<&K> {
         self.from
     }
@@ -221,10 +598,13 @@ impl<'a,K: Key> KeyIterator<'a,K> {
         KeyIterator { inner: Iterator::new(database, options) }
     }
 
+    pub(crate) fn from_inner(inner: Iterator<'a, K>) -> KeyIterator<'a, K> {
+        KeyIterator { inner }
+    }
+
     /// return the last element of the iterator
-    pub fn last(self) -> Option<K> {
-        self.seek_to_last();
-        Some(self.key())
+    pub fn last(mut self) -> Option<K> {
+        self.next_back()
     }
 }
 
@@ -234,6 +614,10 @@ impl<'a,K: Key> LevelDBIterator<'a, K> for KeyIterator<'a,K> {
         self.inner.iter.ptr
     }
 
+    fn status(&self) -> Result<(), Error> {
+        self.inner.status()
+    }
+
     #[inline]
     fn start(&self) -> bool {
         self.inner.start
@@ -244,14 +628,40 @@ impl<'a,K: Key> LevelDBIterator<'a, K> for KeyIterator<'a,K> {
         self.inner.start = false
     }
 
-    fn from(mut self, key: &'a K) -> Self {
-        self.inner.from = Some(key);
-        self
+    fn advance(&mut self) -> bool {
+        self.inner.step_forward()
     }
 
-    fn to(mut self, key: &'a K) -> Self {
-        self.inner.to = Some(key);
-        self
+    fn prev(&mut self) -> bool {
+        self.inner.step_backward()
+    }
+
+    fn from(self, key: &'a K) -> Self {
+        Self { inner: self.inner.from(key) }
+    }
+
+    fn to(self, key: &'a K) -> Self {
+        Self { inner: self.inner.to(key) }
+    }
+
+    fn to_exclusive(self, key: &'a K) -> Self {
+        Self { inner: self.inner.to_exclusive(key) }
+    }
+
+    fn key_bytes(&self) -> &[u8] {
+        self.inner.key_bytes()
+    }
+
+    fn seek_to_first(&mut self) {
+        self.inner.seek_to_first()
+    }
+
+    fn seek_to_last(&mut self) {
+        self.inner.seek_to_last()
+    }
+
+    fn seek(&mut self, key: &K) {
+        self.inner.seek(key)
     }
 
     fn from_key(&self) -> Option<&K> {
@@ -268,10 +678,17 @@ impl<'a,K: Key> ValueIterator<'a,K> {
         ValueIterator { inner: Iterator::new(database, options) }
     }
 
+    pub(crate) fn from_inner(inner: Iterator<'a, K>) -> ValueIterator<'a, K> {
+        ValueIterator { inner }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        self.inner.path()
+    }
+
     /// return the last element of the iterator
-    pub fn last(self) -> Option<Vec<u8>> {
-        self.seek_to_last();
-        Some(self.value())
+    pub fn last(mut self) -> Option<Vec<u8>> {
+        self.next_back()
     }
 }
 
@@ -281,6 +698,10 @@ impl<'a,K: Key> LevelDBIterator<'a, K> for ValueIterator<'a,K> {
         self.inner.iter.ptr
     }
 
+    fn status(&self) -> Result<(), Error> {
+        self.inner.status()
+    }
+
     #[inline]
     fn start(&self) -> bool {
         self.inner.start
@@ -291,14 +712,40 @@ impl<'a,K: Key> LevelDBIterator<'a, K> for ValueIterator<'a,K> {
         self.inner.start = false
     }
 
-    fn from(mut self, key: &'a K) -> Self {
-        self.inner.from = Some(key);
-        self
+    fn advance(&mut self) -> bool {
+        self.inner.step_forward()
     }
 
-    fn to(mut self, key: &'a K) -> Self {
-        self.inner.to = Some(key);
-        self
+    fn prev(&mut self) -> bool {
+        self.inner.step_backward()
+    }
+
+    fn from(self, key: &'a K) -> Self {
+        Self { inner: self.inner.from(key) }
+    }
+
+    fn to(self, key: &'a K) -> Self {
+        Self { inner: self.inner.to(key) }
+    }
+
+    fn to_exclusive(self, key: &'a K) -> Self {
+        Self { inner: self.inner.to_exclusive(key) }
+    }
+
+    fn key_bytes(&self) -> &[u8] {
+        self.inner.key_bytes()
+    }
+
+    fn seek_to_first(&mut self) {
+        self.inner.seek_to_first()
+    }
+
+    fn seek_to_last(&mut self) {
+        self.inner.seek_to_last()
+    }
+
+    fn seek(&mut self, key: &K) {
+        self.inner.seek(key)
     }
 
     fn from_key(&self) -> Option<&K> {
@@ -345,3 +792,33 @@ impl<'a, K: Key> iter::Iterator for ValueIterator<'a,K> {
         }
     }
 }
+
+impl<'a,K: Key> iter::DoubleEndedIterator for Iterator<'a,K> {
+    fn next_back(&mut self) -> Option<(K, Vec<u8>)> {
+        if self.prev() {
+            Some((self.key(), self.value()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, K: Key> iter::DoubleEndedIterator for KeyIterator<'a,K> {
+    fn next_back(&mut self) -> Option<K> {
+        if self.prev() {
+            Some(self.key())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, K: Key> iter::DoubleEndedIterator for ValueIterator<'a,K> {
+    fn next_back(&mut self) -> Option<Vec<u8>> {
+        if self.prev() {
+            Some(self.value())
+        } else {
+            None
+        }
+    }
+}
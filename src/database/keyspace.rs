@@ -0,0 +1,154 @@
+//! Partitioning of a database into tables.
+//!
+//! leveldb has a single, flat keyspace. A `Keyspace` splits it into named
+//! tables by transparently prefixing all keys of a table with its name.
+//! Each `Table` offers the usual `KV` and `Iterable` access confined to its
+//! own keys, and batches can combine writes to several tables atomically.
+//!
+//! Table prefixes rely on keys sharing a prefix being stored next to each
+//! other, which only holds for bytewise orderings like the default
+//! comparator or `BytesComparator<Bytewise, K>`. Databases opened with any
+//! other comparator are rejected. Once partitioned, the database should
+//! only be accessed through its tables.
+use std::borrow::Borrow;
+
+use super::Database;
+use super::key::Key;
+use super::kv::KV;
+use super::bytes::Bytes;
+use super::batch::{Batch, Writebatch};
+use super::error::{Error, ErrorKind};
+use super::iterator::{Iterable, Iterator, KeyIterator, ValueIterator};
+use super::options::{ReadOptions, WriteOptions};
+
+/// A database partitioned into tables.
+pub struct Keyspace<K: Key> {
+    database: Database<K>,
+}
+
+/// A table within a `Keyspace`.
+///
+/// All keys of the table are stored prefixed by the table name.
+pub struct Table<'a, K: Key + 'a> {
+    database: &'a Database<K>,
+    prefix: Vec<u8>,
+}
+
+/// Writes to a single table, recorded in a shared `Writebatch`.
+pub struct TableWritebatch<'a, 'b, K: Key + 'a + 'b> {
+    table: &'b Table<'a, K>,
+    batch: &'b mut Writebatch<K>,
+}
+
+impl<K: Key> Keyspace<K> {
+    /// Partition a database into tables.
+    ///
+    /// Tables rely on keys sharing a prefix being stored next to each other,
+    /// so this fails with `ErrorKind::InvalidArgument` if the database was
+    /// opened with a comparator that does not order keys bytewise, see
+    /// `Comparator::bytewise`.
+    pub fn new(database: Database<K>) -> Result<Keyspace<K>, Error> {
+        if database.comparator.as_ref().map(|c| c.bytewise) == Some(false) {
+            return Err(Error::new_with_kind(ErrorKind::InvalidArgument,
+                                            "keyspaces require a bytewise comparator".to_string())
+                .with_path(&database.path));
+        }
+        Ok(Keyspace { database })
+    }
+
+    /// Access the table with the given name.
+    ///
+    /// Table names must not contain NUL bytes, as a NUL byte terminates
+    /// the name in the key prefix.
+    pub fn table<'a>(&'a self, name: &str) -> Table<'a, K> {
+        assert!(!name.contains('\0'), "table names must not contain NUL bytes");
+        let mut prefix = name.as_bytes().to_vec();
+        prefix.push(0);
+        Table {
+            database: &self.database,
+            prefix,
+        }
+    }
+
+    /// The underlying database.
+    pub fn database(&self) -> &Database<K> {
+        &self.database
+    }
+
+    /// Unwrap the underlying database.
+    pub fn into_inner(self) -> Database<K> {
+        self.database
+    }
+}
+
+impl<K: Key> Batch<K> for Keyspace<K> {
+    /// Write a batch spanning any number of tables atomically.
+    fn write(&self, options: WriteOptions, batch: &Writebatch<K>) -> Result<(), Error> {
+        self.database.write(options, batch)
+    }
+}
+
+impl<'a, K: Key> Table<'a, K> {
+    /// Record writes to this table in `batch`.
+    ///
+    /// The batch can be shared between tables and is committed through
+    /// `Keyspace::write`.
+    pub fn writebatch<'b>(&'b self, batch: &'b mut Writebatch<K>) -> TableWritebatch<'a, 'b, K> {
+        TableWritebatch { table: self, batch }
+    }
+
+    fn table_key<BK: Borrow<K>>(&self, key: BK) -> Vec<u8> {
+        key.borrow().as_slice(|k| [&self.prefix[..], k].concat())
+    }
+}
+
+impl<'a, K: Key> KV<K> for Table<'a, K> {
+    fn get<'r, BK: Borrow<K>>(&self, options: ReadOptions<'r, K>, key: BK) -> Result<Option<Vec<u8>>, Error> {
+        self.get_bytes(options, key).map(|val| val.map(Into::into))
+    }
+
+    fn get_bytes<'r, BK: Borrow<K>>(&self, options: ReadOptions<'r, K>, key: BK) -> Result<Option<Bytes>, Error> {
+        self.database.get_bytes_raw(&options, &self.table_key(key))
+    }
+
+    fn put<BK: Borrow<K>>(&self, options: WriteOptions, key: BK, value: &[u8]) -> Result<(), Error> {
+        self.database.put_raw(options, &self.table_key(key), value)
+    }
+
+    fn delete<BK: Borrow<K>>(&self, options: WriteOptions, key: BK) -> Result<(), Error> {
+        self.database.delete_raw(options, &self.table_key(key))
+    }
+}
+
+impl<'a, K: Key + 'a> Iterable<'a, K> for Table<'a, K> {
+    fn iter(&'a self, options: ReadOptions<'a, K>) -> Iterator<'a, K> {
+        Iterator::with_prefix(self.database, options, &self.prefix, &self.prefix)
+    }
+
+    fn keys_iter(&'a self, options: ReadOptions<'a, K>) -> KeyIterator<'a, K> {
+        KeyIterator::from_inner(self.iter(options))
+    }
+
+    fn value_iter(&'a self, options: ReadOptions<'a, K>) -> ValueIterator<'a, K> {
+        ValueIterator::from_inner(self.iter(options))
+    }
+
+    fn prefix_iter(&'a self, options: ReadOptions<'a, K>, prefix: &[u8]) -> Iterator<'a, K> {
+        let range_prefix = [&self.prefix[..], prefix].concat();
+        Iterator::with_prefix(self.database, options, &self.prefix, &range_prefix)
+    }
+}
+
+impl<'a, 'b, K: Key> TableWritebatch<'a, 'b, K> {
+    /// Batch a put operation
+    pub fn put(&mut self, key: K, value: &[u8]) {
+        let key = self.table.table_key(key);
+        self.batch.put_raw(&key, value)
+    }
+
+    /// Batch a delete operation
+    pub fn delete(&mut self, key: K) {
+        let key = self.table.table_key(key);
+        self.batch.delete_raw(&key)
+    }
+}
@@ -3,13 +3,14 @@
 use super::Database;
 
 use options::{WriteOptions, ReadOptions, c_writeoptions, c_readoptions};
-use super::error::Error;
+use super::error::{Error, Operation};
 use database::key::Key;
 use std::ptr;
 use std::borrow::Borrow;
 use libc::{c_char, size_t};
 use leveldb_sys::*;
 use super::bytes::Bytes;
+use super::batch::{Batch, Writebatch};
 
 /// Key-Value-Access to the leveldb database, providing
 /// a basic interface.
@@ -54,26 +55,7 @@ impl<K: Key> KV<K> for Database<K> {
     /// The database will be synced to disc if `options.sync == true`. This is
     /// NOT the default.
     fn put<BK: Borrow<K>>(&self, options: WriteOptions, key: BK, value: &[u8]) -> Result<(), Error> {
-        unsafe {
-            key.borrow().as_slice(|k| {
-                let mut error = ptr::null_mut();
-                let c_writeoptions = c_writeoptions(options);
-                leveldb_put(self.database.ptr,
-                            c_writeoptions,
-                            k.as_ptr() as *mut c_char,
-                            k.len() as size_t,
-                            value.as_ptr() as *mut c_char,
-                            value.len() as size_t,
-                            &mut error);
-                leveldb_writeoptions_destroy(c_writeoptions);
-
-                if error == ptr::null_mut() {
-                    Ok(())
-                } else {
-                    Err(Error::new_from_i8(error))
-                }
-            })
-        }
+        key.borrow().as_slice(|k| self.put_raw(options, k, value))
     }
 
     /// delete a value from the database.
@@ -83,49 +65,91 @@ impl<K: Key> KV<K> for Database<K> {
     /// The database will be synced to disc if `options.sync == true`. This is
     /// NOT the default.
     fn delete<BK: Borrow<K>>(&self, options: WriteOptions, key: BK) -> Result<(), Error> {
-        unsafe {
-            key.borrow().as_slice(|k| {
-                let mut error = ptr::null_mut();
-                let c_writeoptions = c_writeoptions(options);
-                leveldb_delete(self.database.ptr,
-                               c_writeoptions,
-                               k.as_ptr() as *mut c_char,
-                               k.len() as size_t,
-                               &mut error);
-                leveldb_writeoptions_destroy(c_writeoptions);
-                if error == ptr::null_mut() {
-                    Ok(())
-                } else {
-                    Err(Error::new_from_i8(error))
-                }
-            })
-        }
+        key.borrow().as_slice(|k| self.delete_raw(options, k))
     }
 
     fn get_bytes<'a, BK: Borrow<K>>(&self, options: ReadOptions<'a, K>, key: BK) -> Result<Option<Bytes>, Error> {
+        key.borrow().as_slice(|k| self.get_bytes_raw(&options, k))
+    }
+
+    fn get<'a, BK: Borrow<K>>(&self, options: ReadOptions<'a, K>, key: BK) -> Result<Option<Vec<u8>>, Error> {
+        self.get_bytes(options, key).map(|val| val.map(Into::into))
+    }
+}
+
+// Access by raw keys, used to implement `KV` as well as views
+// that encode keys differently, e.g. tables.
+impl<K: Key> Database<K> {
+    pub(crate) fn put_raw(&self, options: WriteOptions, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        if self.changes.is_active() {
+            // publish the put like any other batch
+            let mut batch = Writebatch::new();
+            batch.put_raw(key, value);
+            return self.write(options, &batch).map_err(|e| e.with_operation(Operation::Put));
+        }
         unsafe {
-            key.borrow().as_slice(|k| {
-                let mut error = ptr::null_mut();
-                let mut length: size_t = 0;
-                let c_readoptions = c_readoptions(&options);
-                let result = leveldb_get(self.database.ptr,
-                                         c_readoptions,
-                                         k.as_ptr() as *mut c_char,
-                                         k.len() as size_t,
-                                         &mut length,
-                                         &mut error);
-                leveldb_readoptions_destroy(c_readoptions);
+            let mut error = ptr::null_mut();
+            let c_writeoptions = c_writeoptions(options);
+            leveldb_put(self.database.ptr,
+                        c_writeoptions,
+                        key.as_ptr() as *mut c_char,
+                        key.len() as size_t,
+                        value.as_ptr() as *mut c_char,
+                        value.len() as size_t,
+                        &mut error);
+            leveldb_writeoptions_destroy(c_writeoptions);
 
-                if error == ptr::null_mut() {
-                    Ok(Bytes::from_raw(result as *mut u8, length))
-                } else {
-                    Err(Error::new_from_i8(error))
-                }
-            })
+            if error == ptr::null_mut() {
+                self.check_panics(Operation::Put)
+            } else {
+                Err(Error::new_from_i8(error).with_operation(Operation::Put).with_path(&self.path))
+            }
         }
     }
 
-    fn get<'a, BK: Borrow<K>>(&self, options: ReadOptions<'a, K>, key: BK) -> Result<Option<Vec<u8>>, Error> {
-        self.get_bytes(options, key).map(|val| val.map(Into::into))
+    pub(crate) fn delete_raw(&self, options: WriteOptions, key: &[u8]) -> Result<(), Error> {
+        if self.changes.is_active() {
+            let mut batch = Writebatch::new();
+            batch.delete_raw(key);
+            return self.write(options, &batch).map_err(|e| e.with_operation(Operation::Delete));
+        }
+        unsafe {
+            let mut error = ptr::null_mut();
+            let c_writeoptions = c_writeoptions(options);
+            leveldb_delete(self.database.ptr,
+                           c_writeoptions,
+                           key.as_ptr() as *mut c_char,
+                           key.len() as size_t,
+                           &mut error);
+            leveldb_writeoptions_destroy(c_writeoptions);
+            if error == ptr::null_mut() {
+                self.check_panics(Operation::Delete)
+            } else {
+                Err(Error::new_from_i8(error).with_operation(Operation::Delete).with_path(&self.path))
+            }
+        }
+    }
+
+    pub(crate) fn get_bytes_raw<'a>(&self, options: &ReadOptions<'a, K>, key: &[u8]) -> Result<Option<Bytes>, Error> {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let mut length: size_t = 0;
+            let c_readoptions = c_readoptions(options);
+            let result = leveldb_get(self.database.ptr,
+                                     c_readoptions,
+                                     key.as_ptr() as *mut c_char,
+                                     key.len() as size_t,
+                                     &mut length,
+                                     &mut error);
+            leveldb_readoptions_destroy(c_readoptions);
+
+            if error == ptr::null_mut() {
+                let bytes = Bytes::from_raw(result as *mut u8, length);
+                self.check_panics(Operation::Get)?;
+                Ok(bytes)
+            } else {
+                Err(Error::new_from_i8(error).with_operation(Operation::Get).with_path(&self.path))
+            }
+        }
     }
 }
@@ -0,0 +1,91 @@
+//! Structs to receive leveldb's internal log output.
+//!
+//! By default, leveldb writes information about compactions and recovery
+//! into a `LOG` file inside the database directory. Setting a `Logger` in
+//! the `Options` routes these messages into Rust instead.
+use leveldb_sys::leveldb_logger_t;
+use libc::{c_char, c_void, size_t};
+use std::slice;
+use std::sync::Arc;
+use database::error::Error;
+use database::panic::{Guarded, PanicSlot, catch_destructor};
+
+// provided by src/logger.cc
+extern "C" {
+    fn leveldb_rs_logger_create(state: *mut c_void,
+                                log: extern "C" fn(*mut c_void, *const c_char, size_t),
+                                destructor: extern "C" fn(*mut c_void))
+                                -> *mut leveldb_logger_t;
+    fn leveldb_rs_logger_destroy(logger: *mut leveldb_logger_t);
+}
+
+#[allow(missing_docs)]
+struct RawLogger {
+    ptr: *mut leveldb_logger_t,
+}
+
+impl Drop for RawLogger {
+    fn drop(&mut self) {
+        unsafe {
+            leveldb_rs_logger_destroy(self.ptr);
+        }
+    }
+}
+
+/// Represents a leveldb info logger
+pub struct Logger {
+    raw: RawLogger,
+    // receives panics of the callback
+    panic: Arc<PanicSlot>,
+}
+
+impl Logger {
+    /// Create a logger passing every message to `callback`.
+    ///
+    /// The callback is invoked from leveldb's background threads.
+    pub fn new<F: Fn(&str) + Send + Sync + 'static>(callback: F) -> Logger {
+        let guarded = Guarded::new(callback);
+        let panic = guarded.panic.clone();
+        let state = Box::into_raw(Box::new(guarded)) as *mut c_void;
+        let logger = unsafe {
+            leveldb_rs_logger_create(state, log_callback::<F>, destructor::<F>)
+        };
+        Logger {
+            raw: RawLogger { ptr: logger },
+            panic,
+        }
+    }
+
+    /// Create a logger forwarding messages to the `log` crate.
+    ///
+    /// Messages are logged at info level, using the target `leveldb`.
+    pub fn log() -> Logger {
+        Logger::new(|message| info!(target: "leveldb", "{}", message))
+    }
+
+    #[allow(missing_docs)]
+    pub fn raw_ptr(&self) -> *mut leveldb_logger_t {
+        self.raw.ptr
+    }
+
+    /// Report a panic caught in the callback, if any.
+    pub(crate) fn check_panic(&self) -> Result<(), Error> {
+        self.panic.check()
+    }
+}
+
+extern "C" fn log_callback<F: Fn(&str)>(state: *mut c_void,
+                                        message: *const c_char,
+                                        length: size_t) {
+    unsafe {
+        let callback = &*(state as *mut Guarded<F>);
+        let bytes = slice::from_raw_parts::<u8>(message as *const u8, length);
+        callback.panic.catch(|| (callback.inner)(&String::from_utf8_lossy(bytes)), || ());
+    }
+}
+
+extern "C" fn destructor<F>(state: *mut c_void) {
+    let x: Box<Guarded<F>> = unsafe { Box::from_raw(state as *mut Guarded<F>) };
+    // let the Box fall out of scope and run the F's destructor
+    catch_destructor(|| drop(x));
+}
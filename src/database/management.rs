@@ -1,17 +1,18 @@
 //! Management functions, e.g. for destroying and reparing a database.
 use options::{Options, c_options};
-use error::Error;
-use std::ffi::CString;
+use error::{Error, Operation};
 use std::ptr;
 use std::path::Path;
+use database::c_path;
 
 use leveldb_sys::{leveldb_destroy_db, leveldb_repair_db};
 
 /// destroy a database. You shouldn't hold a handle on the database anywhere at that time.
-pub fn destroy(name: &Path, options: Options) -> Result<(), Error> {
+pub fn destroy<P: AsRef<Path>>(name: P, options: Options) -> Result<(), Error> {
+    let name = name.as_ref();
+    let c_string = c_path(name).map_err(|e| e.with_operation(Operation::Destroy))?;
     let mut error = ptr::null_mut();
     unsafe {
-        let c_string = CString::new(name.to_str().unwrap()).unwrap();
         let c_options = c_options(&options, None);
         leveldb_destroy_db(c_options,
                            c_string.as_bytes_with_nul().as_ptr() as *const i8,
@@ -20,16 +21,17 @@ pub fn destroy(name: &Path, options: Options) -> Result<(), Error> {
         if error == ptr::null_mut() {
             Ok(())
         } else {
-            Err(Error::new_from_i8(error))
+            Err(Error::new_from_i8(error).with_operation(Operation::Destroy).with_path(name))
         }
     }
 }
 
 /// repair the database. The database should be closed at this moment.
-pub fn repair(name: &Path, options: Options) -> Result<(), Error> {
+pub fn repair<P: AsRef<Path>>(name: P, options: Options) -> Result<(), Error> {
+    let name = name.as_ref();
+    let c_string = c_path(name).map_err(|e| e.with_operation(Operation::Repair))?;
     let mut error = ptr::null_mut();
     unsafe {
-        let c_string = CString::new(name.to_str().unwrap()).unwrap();
         let c_options = c_options(&options, None);
         leveldb_repair_db(c_options,
                           c_string.as_bytes_with_nul().as_ptr() as *const i8,
@@ -38,7 +40,7 @@ pub fn repair(name: &Path, options: Options) -> Result<(), Error> {
         if error == ptr::null_mut() {
             Ok(())
         } else {
-            Err(Error::new_from_i8(error))
+            Err(Error::new_from_i8(error).with_operation(Operation::Repair).with_path(name))
         }
     }
 }
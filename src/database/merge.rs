@@ -0,0 +1,132 @@
+//! Read-modify-write updates
+//!
+//! leveldb has no merge operators. `Merge` emulates them by reading the
+//! current value, computing the new value in Rust and committing it through
+//! a `Writebatch`. Updates to the same key are serialized through a set of
+//! striped locks held by the database, so concurrent updates do not lose
+//! writes.
+//!
+//! Only updates through `Merge` are serialized against each other. Plain
+//! `put` and `delete` calls to a key that is concurrently updated may still
+//! be overwritten.
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+use super::Database;
+use super::key::Key;
+use super::batch::{Batch, Writebatch};
+use super::error::{Error, ErrorKind};
+use super::options::{ReadOptions, WriteOptions};
+
+const LOCK_STRIPES: usize = 64;
+
+/// A user-defined operation combining an existing value with an operand.
+///
+/// Set it as `Options::merge_operator` to use `Merge::merge`.
+pub trait MergeOperator: Send + Sync {
+    /// Compute the new value of `key` from its `existing` value, if any,
+    /// and the `operand` passed to `Merge::merge`.
+    ///
+    /// Returning `None` deletes the key.
+    fn merge(&self, key: &[u8], existing: Option<&[u8]>, operand: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// Atomic read-modify-write access to the database
+pub trait Merge<K: Key> {
+    /// Replace the value of `key` with the result of `f`, applied to its
+    /// current value.
+    ///
+    /// Returning `None` from `f` deletes the key. Returns the new value.
+    fn update<BK, F>(&self, options: WriteOptions, key: BK, f: F) -> Result<Option<Vec<u8>>, Error>
+        where BK: Borrow<K>,
+              F: FnOnce(Option<&[u8]>) -> Option<Vec<u8>>;
+
+    /// Combine the value of `key` with `operand`, using the database's
+    /// merge operator.
+    ///
+    /// Fails if the database was opened without a merge operator.
+    fn merge<BK: Borrow<K>>(&self, options: WriteOptions, key: BK, operand: &[u8]) -> Result<(), Error>;
+}
+
+/// Locks serializing updates of the same key.
+pub(crate) struct UpdateLocks {
+    stripes: Vec<Mutex<()>>,
+}
+
+impl UpdateLocks {
+    pub(crate) fn new() -> UpdateLocks {
+        UpdateLocks { stripes: (0..LOCK_STRIPES).map(|_| Mutex::new(())).collect() }
+    }
+
+    pub(crate) fn lock(&self, key: &[u8]) -> MutexGuard<'_, ()> {
+        self.lock_stripe(self.stripe(key))
+    }
+
+    /// Lock all given keys at once.
+    ///
+    /// Stripes are always taken in the same order, so callers locking
+    /// overlapping sets of keys cannot deadlock.
+    pub(crate) fn lock_all<'k, I>(&self, keys: I) -> Vec<MutexGuard<'_, ()>>
+        where I: IntoIterator<Item = &'k [u8]>
+    {
+        let mut stripes: Vec<usize> = keys.into_iter().map(|k| self.stripe(k)).collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+        stripes.into_iter().map(|s| self.lock_stripe(s)).collect()
+    }
+
+    fn stripe(&self, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.stripes.len()
+    }
+
+    fn lock_stripe(&self, stripe: usize) -> MutexGuard<'_, ()> {
+        // the lock guards no data, so a panic while holding it leaves
+        // nothing inconsistent behind
+        self.stripes[stripe].lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K: Key> Database<K> {
+    fn update_raw<F>(&self, options: WriteOptions, key: &[u8], f: F) -> Result<Option<Vec<u8>>, Error>
+        where F: FnOnce(Option<&[u8]>) -> Option<Vec<u8>>
+    {
+        let _guard = self.update_locks.lock(key);
+        let existing = self.get_bytes_raw(&ReadOptions::new(), key)?;
+        let value = f(existing.as_ref().map(|v| &v[..]));
+
+        let mut batch = Writebatch::new();
+        match value {
+            Some(ref v) => batch.put_raw(key, v),
+            None => batch.delete_raw(key),
+        }
+        self.write(options, &batch)?;
+        Ok(value)
+    }
+}
+
+impl<K: Key> Merge<K> for Database<K> {
+    fn update<BK, F>(&self, options: WriteOptions, key: BK, f: F) -> Result<Option<Vec<u8>>, Error>
+        where BK: Borrow<K>,
+              F: FnOnce(Option<&[u8]>) -> Option<Vec<u8>>
+    {
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        self.update_raw(options, &key, f)
+    }
+
+    fn merge<BK: Borrow<K>>(&self, options: WriteOptions, key: BK, operand: &[u8]) -> Result<(), Error> {
+        let operator = match self.options.merge_operator {
+            Some(ref operator) => operator,
+            None => {
+                return Err(Error::new_with_kind(ErrorKind::InvalidArgument,
+                                                "database has no merge operator".to_string()))
+            }
+        };
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        self.update_raw(options, &key, |existing| operator.merge(&key, existing, operand))
+            .map(|_| ())
+    }
+}
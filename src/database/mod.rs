@@ -5,13 +5,17 @@ extern crate db_key as key;
 use leveldb_sys::*;
 
 use self::options::{Options, c_options};
-use self::error::Error;
+use self::error::{Error, ErrorKind, Operation};
 use std::ffi::CString;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use std::ptr;
-use comparator::{Comparator, create_comparator};
+use std::cmp::Ordering;
+use libc::c_void;
+use comparator::{Comparator, create_comparator_with_state, compare_fn};
+use self::merge::UpdateLocks;
+use self::changes::ChangeFeed;
 use self::key::Key;
 
 use std::marker::PhantomData;
@@ -22,11 +26,24 @@ pub mod iterator;
 pub mod comparator;
 pub mod snapshots;
 pub mod cache;
+pub mod filter;
+pub mod logger;
 pub mod kv;
 pub mod batch;
 pub mod management;
 pub mod compaction;
 pub mod bytes;
+pub mod properties;
+pub mod sizes;
+pub mod typed;
+pub mod keyspace;
+pub mod delete_range;
+pub mod merge;
+pub mod transaction;
+pub mod indexed_batch;
+pub mod changes;
+pub mod backup;
+mod panic;
 
 #[allow(missing_docs)]
 struct RawDB {
@@ -45,6 +62,12 @@ impl Drop for RawDB {
 #[allow(missing_docs)]
 struct RawComparator {
     ptr: *mut leveldb_comparator_t,
+    // the comparator state handed to leveldb and a function comparing
+    // keys with it, to compare keys from Rust in the same order leveldb does
+    state: *mut c_void,
+    compare: unsafe fn(*mut c_void, &[u8], &[u8]) -> Ordering,
+    // whether the comparator orders keys like the default comparator
+    bytewise: bool,
 }
 
 impl Drop for RawComparator {
@@ -69,14 +92,19 @@ impl Drop for RawComparator {
 /// internally.
 pub struct Database<K: Key> {
     database: RawDB,
+    // the directory the database was opened from
+    path: PathBuf,
     // this holds a reference passed into leveldb
     // it is never read from Rust, but must be kept around
     #[allow(dead_code)]
     comparator: Option<RawComparator>,
     // these hold multiple references that are used by the leveldb library
     // and should survive as long as the database lives
-    #[allow(dead_code)]
     options: Options,
+    // serializes read-modify-write updates of the same key
+    update_locks: UpdateLocks,
+    // listeners notified of committed mutations
+    changes: ChangeFeed<K>,
     marker: PhantomData<K>,
 }
 
@@ -85,29 +113,53 @@ unsafe impl<K: Key> Send for Database<K> {}
 
 impl<K: Key> Database<K> {
     fn new(database: *mut leveldb_t,
+           path: &Path,
            options: Options,
-           comparator: Option<*mut leveldb_comparator_t>)
+           comparator: Option<RawComparator>)
            -> Database<K> {
-        let raw_comp = match comparator {
-            Some(p) => Some(RawComparator { ptr: p }),
-            None => None,
-        };
         Database {
             database: RawDB { ptr: database },
-            comparator: raw_comp,
+            path: path.to_path_buf(),
+            comparator,
             options: options,
+            update_locks: UpdateLocks::new(),
+            changes: ChangeFeed::new(),
             marker: PhantomData,
         }
     }
 
+    /// Compare two raw keys using the ordering of this database.
+    ///
+    /// This is the bytewise ordering unless the database was opened
+    /// with a custom comparator.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match self.comparator {
+            // the state lives as long as the comparator
+            Some(ref c) => unsafe { (c.compare)(c.state, a, b) },
+            None => a.cmp(b),
+        }
+    }
+
+    /// Report a panic caught in a callback of the filter policy or
+    /// logger of this database as an error of `operation`.
+    ///
+    /// These callbacks may run on leveldb's background threads, so the
+    /// panic is reported by the next operation checking for it.
+    pub(crate) fn check_panics(&self, operation: Operation) -> Result<(), Error> {
+        self.options
+            .check_panics()
+            .map_err(|e| e.with_operation(operation).with_path(&self.path))
+    }
+
     /// Open a new database
     ///
     /// If the database is missing, the behaviour depends on `options.create_if_missing`.
     /// The database will be created using the settings given in `options`.
-    pub fn open(name: &Path, options: Options) -> Result<Database<K>, Error> {
+    pub fn open<P: AsRef<Path>>(name: P, options: Options) -> Result<Database<K>, Error> {
+        let name = name.as_ref();
+        let c_string = c_path(name).map_err(|e| e.with_operation(Operation::Open))?;
         let mut error = ptr::null_mut();
         unsafe {
-            let c_string = CString::new(name.to_str().unwrap()).unwrap();
             let c_options = c_options(&options, None);
             let db = leveldb_open(c_options as *const leveldb_options_t,
                                   c_string.as_bytes_with_nul().as_ptr() as *const i8,
@@ -115,9 +167,11 @@ impl<K: Key> Database<K> {
             leveldb_options_destroy(c_options);
 
             if error == ptr::null_mut() {
-                Ok(Database::new(db, options, None))
+                let database = Database::new(db, name, options, None);
+                database.check_panics(Operation::Open)?;
+                Ok(database)
             } else {
-                Err(Error::new_from_i8(error))
+                Err(Error::new_from_i8(error).with_operation(Operation::Open).with_path(name))
             }
         }
     }
@@ -130,25 +184,61 @@ impl<K: Key> Database<K> {
     /// The comparator must implement a total ordering over the keyspace.
     ///
     /// For keys that implement Ord, consider the `OrdComparator`.
-    pub fn open_with_comparator<C: Comparator<K = K>>(name: &Path,
-                                                      options: Options,
-                                                      comparator: C)
-                                                      -> Result<Database<K>, Error> {
+    pub fn open_with_comparator<C>(name: impl AsRef<Path>,
+                                   options: Options,
+                                   comparator: C)
+                                   -> Result<Database<K>, Error>
+        where C: Comparator<K = K>
+    {
+        let name = name.as_ref();
+        let c_string = c_path(name).map_err(|e| e.with_operation(Operation::Open))?;
         let mut error = ptr::null_mut();
-        let comp_ptr = create_comparator(Box::new(comparator));
+        let (comp_ptr, state) = create_comparator_with_state(comparator);
         unsafe {
-            let c_string = CString::new(name.to_str().unwrap()).unwrap();
             let c_options = c_options(&options, Some(comp_ptr));
             let db = leveldb_open(c_options as *const leveldb_options_t,
                                   c_string.as_bytes_with_nul().as_ptr() as *const i8,
                                   &mut error);
             leveldb_options_destroy(c_options);
 
+            let raw_comp = RawComparator {
+                ptr: comp_ptr,
+                state,
+                compare: compare_fn::<C>(),
+                bytewise: C::bytewise(),
+            };
+
             if error == ptr::null_mut() {
-                Ok(Database::new(db, options, Some(comp_ptr)))
+                let database = Database::new(db, name, options, Some(raw_comp));
+                database.check_panics(Operation::Open)?;
+                Ok(database)
             } else {
-                Err(Error::new_from_i8(error))
+                Err(Error::new_from_i8(error).with_operation(Operation::Open).with_path(name))
             }
         }
     }
 }
+
+/// Convert a path into a C string for leveldb.
+///
+/// On Unix, the path is passed on as raw bytes. Elsewhere, it must be
+/// valid Unicode. Paths containing NUL bytes are rejected.
+pub(crate) fn c_path(path: &Path) -> Result<CString, Error> {
+    let invalid = |reason: &str| {
+        Error::new_with_kind(ErrorKind::InvalidArgument, format!("invalid path: {}", reason))
+            .with_path(path)
+    };
+    let bytes = path_bytes(path).ok_or_else(|| invalid("not valid Unicode"))?;
+    CString::new(bytes).map_err(|_| invalid("contains a NUL byte"))
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(path.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Option<Vec<u8>> {
+    path.to_str().map(|s| s.as_bytes().to_vec())
+}
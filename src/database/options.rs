@@ -10,6 +10,10 @@ use libc::size_t;
 use database::snapshots::Snapshot;
 use database::key::Key;
 use database::cache::Cache;
+use database::filter::Filter;
+use database::logger::Logger;
+use database::merge::MergeOperator;
+use database::error::Error;
 
 /// Options to consider when opening a new or pre-existing database.
 ///
@@ -56,6 +60,24 @@ pub struct Options {
     ///
     /// default: None
     pub cache: Option<Cache>,
+    /// A filter policy to reduce disk reads, e.g. `Filter::bloom(10)`.
+    ///
+    /// Databases should be reopened with the same filter policy.
+    ///
+    /// default: None
+    pub filter_policy: Option<Filter>,
+    /// A logger receiving leveldb's internal log output.
+    ///
+    /// If unset, leveldb writes its log into a `LOG` file in the database directory.
+    ///
+    /// default: None
+    pub info_log: Option<Logger>,
+    /// The operator combining values with operands in `Merge::merge`.
+    ///
+    /// It is applied in Rust and never passed to leveldb.
+    ///
+    /// default: None
+    pub merge_operator: Option<Box<dyn MergeOperator>>,
 }
 
 impl Options {
@@ -71,8 +93,22 @@ impl Options {
             block_restart_interval: None,
             compression: Compression::No,
             cache: None,
+            filter_policy: None,
+            info_log: None,
+            merge_operator: None,
         }
     }
+
+    /// Report panics caught in callbacks of the filter policy or logger.
+    pub(crate) fn check_panics(&self) -> Result<(), Error> {
+        if let Some(ref filter) = self.filter_policy {
+            filter.check_panic()?;
+        }
+        if let Some(ref logger) = self.info_log {
+            logger.check_panic()?;
+        }
+        Ok(())
+    }
 }
 
 /// The write options to use for a write operation.
@@ -150,6 +186,12 @@ pub unsafe fn c_options(options: &Options,
     if let Some(ref cache) = options.cache {
         leveldb_options_set_cache(c_options, cache.raw_ptr());
     }
+    if let Some(ref filter) = options.filter_policy {
+        leveldb_options_set_filter_policy(c_options, filter.raw_ptr());
+    }
+    if let Some(ref logger) = options.info_log {
+        leveldb_options_set_info_log(c_options, logger.raw_ptr());
+    }
     c_options
 }
 
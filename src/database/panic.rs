@@ -0,0 +1,146 @@
+//! Panic safety for callbacks invoked by leveldb.
+//!
+//! Unwinding out of an `extern "C"` function into leveldb is undefined
+//! behaviour, so all callbacks catch panics before returning to leveldb.
+//! What happens next depends on the callback:
+//!
+//! * Comparators have no safe fallback. Continuing with any other ordering
+//!   would let leveldb write data in the wrong order, which corrupts the
+//!   database for good, so a comparator panicking in a callback aborts the
+//!   process. Comparisons made from Rust call the comparator directly and
+//!   unwind as usual.
+//! * Filter policies and loggers may run on leveldb's background threads.
+//!   They hand a safe fallback value back to leveldb and store their panic
+//!   in a `PanicSlot` shared with the owning object. The next fallible
+//!   operation on the database reports it as an `ErrorKind::Panicked` error.
+//! * Callbacks invoked synchronously from a call into leveldb, e.g. when
+//!   iterating over a write batch, store their panic for the current thread.
+//!   It is re-raised once that call returns.
+//! * Panics in destructors of callback state are logged and dropped.
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use super::error::{Error, ErrorKind};
+
+type Payload = Box<dyn Any + Send>;
+
+/// A panic caught in a callback that may run on any thread.
+#[derive(Default)]
+pub(crate) struct PanicSlot {
+    payload: Mutex<Option<Payload>>,
+}
+
+/// Callback state along with the slot receiving its panics.
+pub(crate) struct Guarded<T> {
+    pub(crate) inner: T,
+    pub(crate) panic: Arc<PanicSlot>,
+}
+
+impl PanicSlot {
+    /// Run `f`, returning the result of `fallback` if it panics.
+    pub(crate) fn catch<T, F, G>(&self, f: F, fallback: G) -> T
+        where F: FnOnce() -> T,
+              G: FnOnce() -> T
+    {
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(res) => res,
+            Err(payload) => {
+                let mut slot = self.payload.lock().unwrap_or_else(PoisonError::into_inner);
+                // keep the first panic, it is most likely the cause of later ones
+                if slot.is_none() {
+                    *slot = Some(payload);
+                }
+                fallback()
+            }
+        }
+    }
+
+    /// Report a caught panic, if any, as an error.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        let payload = self.payload.lock().unwrap_or_else(PoisonError::into_inner).take();
+        match payload {
+            Some(payload) => {
+                Err(Error::new_with_kind(ErrorKind::Panicked,
+                                         format!("callback panicked: {}", message(&*payload))))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Guarded<T> {
+    pub(crate) fn new(inner: T) -> Guarded<T> {
+        Guarded {
+            inner,
+            panic: Arc::new(PanicSlot::default()),
+        }
+    }
+}
+
+/// Run a callback that has no safe fallback, aborting the process if it
+/// panics.
+pub(crate) fn abort_on_panic<T, F: FnOnce() -> T>(callback: &str, f: F) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            error!(target: "leveldb",
+                   "{} panicked, aborting to protect the database: {}",
+                   callback,
+                   message(&*payload));
+            process::abort()
+        }
+    }
+}
+
+/// Run the destructor of callback state, logging a panic.
+pub(crate) fn catch_destructor<F: FnOnce()>(f: F) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+        error!(target: "leveldb", "destructor of callback panicked: {}", message(&*payload));
+    }
+}
+
+thread_local! {
+    static LOCAL_PANIC: RefCell<Option<Payload>> = RefCell::new(None);
+}
+
+/// Run a callback invoked synchronously on this thread, returning
+/// `fallback` if it panics.
+///
+/// Once a callback panicked, further callbacks are skipped until the panic
+/// is re-raised through `resume_local`.
+pub(crate) fn catch_local<T, F: FnOnce() -> T>(f: F, fallback: T) -> T {
+    if LOCAL_PANIC.with(|p| p.borrow().is_some()) {
+        return fallback;
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            LOCAL_PANIC.with(|p| *p.borrow_mut() = Some(payload));
+            fallback
+        }
+    }
+}
+
+/// Re-raise a panic caught by `catch_local` on this thread, if any.
+pub(crate) fn resume_local() {
+    if let Some(payload) = LOCAL_PANIC.with(|p| p.borrow_mut().take()) {
+        // raising a second panic while unwinding would abort the process
+        if !thread::panicking() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
@@ -0,0 +1,147 @@
+//! Database properties
+//!
+//! leveldb exposes internal statistics through named properties. This module
+//! provides raw access to them as well as typed accessors for the properties
+//! leveldb knows about.
+use leveldb_sys::{leveldb_property_value, leveldb_free};
+use libc::{c_char, c_void};
+use std::ffi::{CString, CStr};
+
+use super::Database;
+use super::key::Key;
+
+/// Compaction statistics of a single level, as reported by `leveldb.stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelStats {
+    /// The level these statistics belong to
+    pub level: usize,
+    /// Number of files at this level
+    pub files: usize,
+    /// Size of the level in MB
+    pub size_mb: f64,
+    /// Time spent compacting this level, in seconds
+    pub time_sec: f64,
+    /// Data read during compactions of this level, in MB
+    pub read_mb: f64,
+    /// Data written during compactions of this level, in MB
+    pub write_mb: f64,
+}
+
+/// An SSTable file, as reported by `leveldb.sstables`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SSTable {
+    /// The level the table lives in
+    pub level: usize,
+    /// The file number of the table
+    pub number: u64,
+    /// The size of the table file in bytes
+    pub size: u64,
+    /// Debug representation of the smallest internal key in the table
+    pub smallest: String,
+    /// Debug representation of the largest internal key in the table
+    pub largest: String,
+}
+
+/// Access to the properties of a database.
+pub trait Properties {
+    /// Query a raw property by its name, e.g. `leveldb.stats`.
+    ///
+    /// Returns `None` if the property is unknown.
+    fn property(&self, name: &str) -> Option<String>;
+
+    /// Compaction statistics for all levels that hold files or have been compacted.
+    fn stats(&self) -> Option<Vec<LevelStats>> {
+        self.property("leveldb.stats").map(|s| parse_stats(&s))
+    }
+
+    /// The number of files at the given level.
+    fn num_files_at_level(&self, level: usize) -> Option<usize> {
+        self.property(&format!("leveldb.num-files-at-level{}", level))
+            .and_then(|s| s.trim().parse().ok())
+    }
+
+    /// All SSTables that make up the current version of the database.
+    fn sstables(&self) -> Option<Vec<SSTable>> {
+        self.property("leveldb.sstables").map(|s| parse_sstables(&s))
+    }
+
+    /// Approximate memory usage of the database in bytes.
+    fn approximate_memory_usage(&self) -> Option<u64> {
+        self.property("leveldb.approximate-memory-usage")
+            .and_then(|s| s.trim().parse().ok())
+    }
+}
+
+impl<K: Key> Properties for Database<K> {
+    fn property(&self, name: &str) -> Option<String> {
+        let c_name = match CString::new(name) {
+            Ok(n) => n,
+            Err(_) => return None,
+        };
+        unsafe {
+            let value = leveldb_property_value(self.database.ptr,
+                                               c_name.as_ptr() as *const c_char);
+            if value.is_null() {
+                None
+            } else {
+                let res = CStr::from_ptr(value).to_string_lossy().into_owned();
+                leveldb_free(value as *mut c_void);
+                Some(res)
+            }
+        }
+    }
+}
+
+fn parse_stats(stats: &str) -> Vec<LevelStats> {
+    // the first three lines are headers
+    stats.lines()
+        .skip(3)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 6 {
+                return None;
+            }
+            Some(LevelStats {
+                level: fields[0].parse().ok()?,
+                files: fields[1].parse().ok()?,
+                size_mb: fields[2].parse().ok()?,
+                time_sec: fields[3].parse().ok()?,
+                read_mb: fields[4].parse().ok()?,
+                write_mb: fields[5].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn parse_sstables(sstables: &str) -> Vec<SSTable> {
+    // Format:
+    //   --- level 1 ---
+    //    17:123['a' @ 1 : 1 .. 'd' @ 4 : 1]
+    let mut level = 0;
+    let mut tables = vec![];
+    for line in sstables.lines() {
+        let line = line.trim();
+        if line.starts_with("--- level ") {
+            level = line.trim_start_matches("--- level ")
+                .trim_end_matches(" ---")
+                .parse()
+                .unwrap_or(level);
+        } else if let Some(table) = parse_sstable(level, line) {
+            tables.push(table);
+        }
+    }
+    tables
+}
+
+fn parse_sstable(level: usize, line: &str) -> Option<SSTable> {
+    let (number, rest) = line.split_once(':')?;
+    let (size, range) = rest.split_once('[')?;
+    let (smallest, largest) = range.strip_suffix(']')?.split_once(" .. ")?;
+    Some(SSTable {
+        level,
+        number: number.parse().ok()?,
+        size: size.parse().ok()?,
+        smallest: smallest.to_string(),
+        largest: largest.to_string(),
+    })
+}
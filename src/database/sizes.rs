@@ -0,0 +1,37 @@
+//! Approximate sizes of key ranges
+use super::Database;
+use super::key::Key;
+use leveldb_sys::leveldb_approximate_sizes;
+use libc::{c_char, c_int, size_t};
+
+/// Estimation of the space used by key ranges.
+pub trait ApproximateSizes<K: Key> {
+    /// Return the approximate file system space used by each of the
+    /// `(start, limit)` ranges, in bytes.
+    ///
+    /// The sizes are estimates based on the on-disk tables, so recently
+    /// written data that has not been compacted yet may not show up.
+    fn approximate_sizes(&self, ranges: &[(K, K)]) -> Vec<u64>;
+}
+
+impl<K: Key> ApproximateSizes<K> for Database<K> {
+    fn approximate_sizes(&self, ranges: &[(K, K)]) -> Vec<u64> {
+        let starts: Vec<Vec<u8>> = ranges.iter().map(|r| r.0.as_slice(|s| s.to_vec())).collect();
+        let limits: Vec<Vec<u8>> = ranges.iter().map(|r| r.1.as_slice(|l| l.to_vec())).collect();
+        let start_ptrs: Vec<*const c_char> = starts.iter().map(|s| s.as_ptr() as *const c_char).collect();
+        let start_lens: Vec<size_t> = starts.iter().map(|s| s.len() as size_t).collect();
+        let limit_ptrs: Vec<*const c_char> = limits.iter().map(|l| l.as_ptr() as *const c_char).collect();
+        let limit_lens: Vec<size_t> = limits.iter().map(|l| l.len() as size_t).collect();
+        let mut sizes = vec![0; ranges.len()];
+        unsafe {
+            leveldb_approximate_sizes(self.database.ptr,
+                                      ranges.len() as c_int,
+                                      start_ptrs.as_ptr(),
+                                      start_lens.as_ptr(),
+                                      limit_ptrs.as_ptr(),
+                                      limit_lens.as_ptr(),
+                                      sizes.as_mut_ptr());
+        }
+        sizes
+    }
+}
@@ -92,4 +92,8 @@ impl<'a, K: Key + 'a> Iterable<'a, K> for Snapshot<'a, K> {
         options.snapshot = Some(self);
         self.database.value_iter(options)
     }
+    fn prefix_iter(&'a self, mut options: ReadOptions<'a, K>, prefix: &[u8]) -> Iterator<'a, K> {
+        options.snapshot = Some(self);
+        self.database.prefix_iter(options, prefix)
+    }
 }
@@ -0,0 +1,157 @@
+//! Optimistic transactions
+//!
+//! A `Transaction` reads from a snapshot taken when it starts and buffers
+//! its writes in a `Writebatch`. On commit, every key it read is checked
+//! against the current state of the database. If any of them changed in
+//! the meantime, the commit fails with `TransactionError::Conflict` and
+//! nothing is written.
+//!
+//! Commits lock the keys they touch using the same locks as `Merge`, so
+//! they are serialized against each other and against merges.
+//!
+//! Conflicts are detected by comparing values, as leveldb keeps no version
+//! per key. A key that was changed and then changed back, e.g. from A to B
+//! and back to A, reads the same as at the snapshot and is not a conflict,
+//! and neither is a write storing the value that was read.
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Database;
+use super::key::Key;
+use super::batch::{Batch, Writebatch};
+use super::error::Error;
+use super::options::{ReadOptions, WriteOptions};
+use super::snapshots::{Snapshot, Snapshots};
+
+/// A transaction over a database.
+///
+/// Conflict detection is value-based: a commit only fails if a key it read
+/// now holds a different value than at the snapshot. It does not detect
+/// keys that were modified and restored to their old value in between.
+pub struct Transaction<'a, K: Key + 'a> {
+    database: &'a Database<K>,
+    snapshot: Snapshot<'a, K>,
+    // raw keys read, with the value seen at the snapshot
+    reads: HashMap<Vec<u8>, Option<Vec<u8>>>,
+    // raw keys written
+    writes: Vec<Vec<u8>>,
+    batch: Writebatch<K>,
+}
+
+/// The reasons a transaction can fail.
+#[derive(Debug)]
+pub enum TransactionError {
+    /// A key read by the transaction was modified by someone else.
+    Conflict,
+    /// The database reported an error.
+    Database(Error),
+}
+
+/// Structs implementing the Transactions trait can run transactions.
+pub trait Transactions<K: Key> {
+    /// Start a new transaction.
+    fn transaction<'a>(&'a self) -> Transaction<'a, K>;
+
+    /// Run `f` in a transaction and commit it, retrying on conflicts.
+    ///
+    /// `f` is run again in a fresh transaction up to `retries` times if
+    /// the commit fails with a conflict. Errors returned by `f` abort the
+    /// transaction without retrying.
+    fn transact<T, F>(&self, options: WriteOptions, retries: usize, f: F) -> Result<T, TransactionError>
+        where F: FnMut(&mut Transaction<K>) -> Result<T, TransactionError>;
+}
+
+impl<K: Key> Transactions<K> for Database<K> {
+    fn transaction<'a>(&'a self) -> Transaction<'a, K> {
+        Transaction {
+            database: self,
+            snapshot: self.snapshot(),
+            reads: HashMap::new(),
+            writes: vec![],
+            batch: Writebatch::new(),
+        }
+    }
+
+    fn transact<T, F>(&self, options: WriteOptions, retries: usize, mut f: F) -> Result<T, TransactionError>
+        where F: FnMut(&mut Transaction<K>) -> Result<T, TransactionError>
+    {
+        let mut attempt = 0;
+        loop {
+            let mut transaction = self.transaction();
+            let res = f(&mut transaction)?;
+            match transaction.commit(options) {
+                Err(TransactionError::Conflict) if attempt < retries => attempt += 1,
+                Err(e) => return Err(e),
+                Ok(()) => return Ok(res),
+            }
+        }
+    }
+}
+
+impl<'a, K: Key> Transaction<'a, K> {
+    /// get a value as of the start of the transaction.
+    ///
+    /// The key is checked for modifications on commit. Writes of this
+    /// transaction are not visible before it is committed.
+    pub fn get<BK: Borrow<K>>(&mut self, key: BK) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        if let Some(value) = self.reads.get(&key) {
+            return Ok(value.clone());
+        }
+        let mut options = ReadOptions::new();
+        options.snapshot = Some(&self.snapshot);
+        let value = self.database
+            .get_bytes_raw(&options, &key)?
+            .map(Into::into);
+        self.reads.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Buffer a put operation until commit.
+    pub fn put<BK: Borrow<K>>(&mut self, key: BK, value: &[u8]) {
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        self.batch.put_raw(&key, value);
+        self.writes.push(key);
+    }
+
+    /// Buffer a delete operation until commit.
+    pub fn delete<BK: Borrow<K>>(&mut self, key: BK) {
+        let key = key.borrow().as_slice(|k| k.to_vec());
+        self.batch.delete_raw(&key);
+        self.writes.push(key);
+    }
+
+    /// Write all buffered operations, unless a key read by the transaction
+    /// holds a different value than when it started.
+    pub fn commit(self, options: WriteOptions) -> Result<(), TransactionError> {
+        let keys = self.reads.keys().chain(self.writes.iter()).map(|k| &k[..]);
+        let _guards = self.database.update_locks.lock_all(keys);
+
+        for (key, value) in &self.reads {
+            let current = self.database.get_bytes_raw(&ReadOptions::new(), key)?;
+            if current.as_ref().map(|v| &v[..]) != value.as_ref().map(|v| &v[..]) {
+                return Err(TransactionError::Conflict);
+            }
+        }
+        self.database.write(options, &self.batch)?;
+        Ok(())
+    }
+}
+
+impl From<Error> for TransactionError {
+    fn from(error: Error) -> TransactionError {
+        TransactionError::Database(error)
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionError::Conflict => write!(f, "transaction conflict"),
+            TransactionError::Database(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl ::std::error::Error for TransactionError {}
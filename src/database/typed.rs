@@ -0,0 +1,331 @@
+//! Typed access to the values of a database.
+//!
+//! `KV` stores and returns plain bytes. `TypedDatabase` wraps a `Database`
+//! and converts values to and from Rust types implementing `Codec`, the
+//! same way keys are converted through `Key`.
+use std::borrow::Borrow;
+use std::iter;
+use std::marker::PhantomData;
+
+use super::Database;
+use super::key::Key;
+use super::kv::KV;
+use super::batch::{Batch, Writebatch};
+use super::error::{Error, ErrorKind, Operation};
+use super::iterator::{Iterable, Iterator, ValueIterator, LevelDBIterator};
+use super::options::{ReadOptions, WriteOptions};
+
+/// Conversion of values to and from their stored representation.
+///
+/// `decode` must succeed on everything `encode` produced. Stored values may
+/// also have been written by other clients or be corrupted, so other input
+/// must be rejected with an error rather than a panic.
+pub trait Codec: Sized {
+    /// Encode the value into bytes.
+    fn encode(&self) -> Vec<u8>;
+    /// Decode a value from bytes produced by `encode`.
+    fn decode(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+impl Codec for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl Codec for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<String, Error> {
+        String::from_utf8(bytes.to_vec()).map_err(|_| {
+            Error::new_with_kind(ErrorKind::Corruption, "value is not valid UTF-8".to_string())
+        })
+    }
+}
+
+macro_rules! int_codec {
+    ($t:ty, $len:expr) => {
+        impl Codec for $t {
+            fn encode(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
+
+            fn decode(bytes: &[u8]) -> Result<$t, Error> {
+                if bytes.len() != $len {
+                    return Err(Error::new_with_kind(ErrorKind::Corruption,
+                                                    format!("value has length {}, expected {}",
+                                                            bytes.len(),
+                                                            $len)));
+                }
+                let mut buf = [0u8; $len];
+                buf.copy_from_slice(bytes);
+                Ok(<$t>::from_be_bytes(buf))
+            }
+        }
+    }
+}
+
+int_codec!(u32, 4);
+int_codec!(i32, 4);
+int_codec!(u64, 8);
+int_codec!(i64, 8);
+
+/// A database storing values of type `V`.
+pub struct TypedDatabase<K: Key, V: Codec> {
+    database: Database<K>,
+    marker: PhantomData<V>,
+}
+
+/// A write batch of typed values.
+pub struct TypedWritebatch<K: Key, V: Codec> {
+    batch: Writebatch<K>,
+    marker: PhantomData<V>,
+}
+
+/// An iterator over keys and typed values.
+///
+/// Iteration stops at the first value that cannot be decoded, see `status`.
+pub struct TypedIterator<'a, K: Key + 'a, V: Codec> {
+    inner: Iterator<'a, K>,
+    error: Option<Error>,
+    marker: PhantomData<V>,
+}
+
+/// An iterator over typed values.
+///
+/// Iteration stops at the first value that cannot be decoded, see `status`.
+pub struct TypedValueIterator<'a, K: Key + 'a, V: Codec> {
+    inner: ValueIterator<'a, K>,
+    error: Option<Error>,
+    marker: PhantomData<V>,
+}
+
+impl<K: Key, V: Codec> TypedDatabase<K, V> {
+    /// Wrap a database, storing values of type `V`.
+    pub fn new(database: Database<K>) -> TypedDatabase<K, V> {
+        TypedDatabase {
+            database,
+            marker: PhantomData,
+        }
+    }
+
+    /// The underlying database, e.g. for taking snapshots.
+    pub fn database(&self) -> &Database<K> {
+        &self.database
+    }
+
+    /// Unwrap the underlying database.
+    pub fn into_inner(self) -> Database<K> {
+        self.database
+    }
+
+    /// get a value from the database.
+    pub fn get<'a, BK: Borrow<K>>(&self,
+                                  options: ReadOptions<'a, K>,
+                                  key: BK)
+                                  -> Result<Option<V>, Error> {
+        self.database
+            .get_bytes(options, key)
+            .and_then(|val| match val {
+                Some(v) => {
+                    V::decode(&v)
+                        .map(Some)
+                        .map_err(|e| e.with_operation(Operation::Get).with_path(&self.database.path))
+                }
+                None => Ok(None),
+            })
+    }
+
+    /// put a value into the database.
+    pub fn put<BK: Borrow<K>>(&self, options: WriteOptions, key: BK, value: &V) -> Result<(), Error> {
+        self.database.put(options, key, &value.encode())
+    }
+
+    /// delete a value from the database.
+    pub fn delete<BK: Borrow<K>>(&self, options: WriteOptions, key: BK) -> Result<(), Error> {
+        self.database.delete(options, key)
+    }
+
+    /// Write a batch to the database, ensuring success for all items or an error
+    pub fn write(&self, options: WriteOptions, batch: &TypedWritebatch<K, V>) -> Result<(), Error> {
+        self.database.write(options, &batch.batch)
+    }
+
+    /// Return an Iterator iterating over (Key, Value) pairs
+    pub fn iter<'a>(&'a self, options: ReadOptions<'a, K>) -> TypedIterator<'a, K, V> {
+        TypedIterator {
+            inner: self.database.iter(options),
+            error: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Return an Iterator iterating over Values only
+    pub fn value_iter<'a>(&'a self, options: ReadOptions<'a, K>) -> TypedValueIterator<'a, K, V> {
+        TypedValueIterator {
+            inner: self.database.value_iter(options),
+            error: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Key, V: Codec> TypedWritebatch<K, V> {
+    /// Create a new writebatch
+    pub fn new() -> TypedWritebatch<K, V> {
+        TypedWritebatch {
+            batch: Writebatch::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Clear the writebatch
+    pub fn clear(&mut self) {
+        self.batch.clear()
+    }
+
+    /// Batch a put operation
+    pub fn put(&mut self, key: K, value: &V) {
+        self.batch.put(key, &value.encode())
+    }
+
+    /// Batch a delete operation
+    pub fn delete(&mut self, key: K) {
+        self.batch.delete(key)
+    }
+}
+
+impl<K: Key, V: Codec> Default for TypedWritebatch<K, V> {
+    fn default() -> TypedWritebatch<K, V> {
+        TypedWritebatch::new()
+    }
+}
+
+impl<'a, K: Key, V: Codec> TypedIterator<'a, K, V> {
+    /// Start iterating at `key`, inclusive.
+    pub fn from(self, key: &'a K) -> Self {
+        TypedIterator { inner: self.inner.from(key), ..self }
+    }
+
+    /// Stop iterating at `key`, inclusive.
+    pub fn to(self, key: &'a K) -> Self {
+        TypedIterator { inner: self.inner.to(key), ..self }
+    }
+
+    /// Stop iterating before `key`, exclusive.
+    pub fn to_exclusive(self, key: &'a K) -> Self {
+        TypedIterator { inner: self.inner.to_exclusive(key), ..self }
+    }
+
+    /// Return the error that stopped the iteration, if any.
+    ///
+    /// This includes values that could not be decoded.
+    pub fn status(&self) -> Result<(), Error> {
+        match self.error {
+            Some(ref e) => Err(e.clone()),
+            None => self.inner.status(),
+        }
+    }
+}
+
+impl<'a, K: Key, V: Codec> TypedValueIterator<'a, K, V> {
+    /// Start iterating at `key`, inclusive.
+    pub fn from(self, key: &'a K) -> Self {
+        TypedValueIterator { inner: self.inner.from(key), ..self }
+    }
+
+    /// Stop iterating at `key`, inclusive.
+    pub fn to(self, key: &'a K) -> Self {
+        TypedValueIterator { inner: self.inner.to(key), ..self }
+    }
+
+    /// Stop iterating before `key`, exclusive.
+    pub fn to_exclusive(self, key: &'a K) -> Self {
+        TypedValueIterator { inner: self.inner.to_exclusive(key), ..self }
+    }
+
+    /// Return the error that stopped the iteration, if any.
+    ///
+    /// This includes values that could not be decoded.
+    pub fn status(&self) -> Result<(), Error> {
+        match self.error {
+            Some(ref e) => Err(e.clone()),
+            None => self.inner.status(),
+        }
+    }
+}
+
+impl<'a, K: Key, V: Codec> TypedIterator<'a, K, V> {
+    fn decode(&mut self, entry: Option<(K, Vec<u8>)>) -> Option<(K, V)> {
+        let (k, v) = entry?;
+        match V::decode(&v) {
+            Ok(v) => Some((k, v)),
+            Err(e) => {
+                self.error = Some(e.with_operation(Operation::Iterate).with_path(self.inner.path()));
+                None
+            }
+        }
+    }
+}
+
+impl<'a, K: Key, V: Codec> iter::Iterator for TypedIterator<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.error.is_some() {
+            return None;
+        }
+        let entry = self.inner.next();
+        self.decode(entry)
+    }
+}
+
+impl<'a, K: Key, V: Codec> iter::DoubleEndedIterator for TypedIterator<'a, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        if self.error.is_some() {
+            return None;
+        }
+        let entry = self.inner.next_back();
+        self.decode(entry)
+    }
+}
+
+impl<'a, K: Key, V: Codec> TypedValueIterator<'a, K, V> {
+    fn decode(&mut self, value: Option<Vec<u8>>) -> Option<V> {
+        match V::decode(&value?) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.error = Some(e.with_operation(Operation::Iterate).with_path(self.inner.path()));
+                None
+            }
+        }
+    }
+}
+
+impl<'a, K: Key, V: Codec> iter::Iterator for TypedValueIterator<'a, K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        if self.error.is_some() {
+            return None;
+        }
+        let value = self.inner.next();
+        self.decode(value)
+    }
+}
+
+impl<'a, K: Key, V: Codec> iter::DoubleEndedIterator for TypedValueIterator<'a, K, V> {
+    fn next_back(&mut self) -> Option<V> {
+        if self.error.is_some() {
+            return None;
+        }
+        let value = self.inner.next_back();
+        self.decode(value)
+    }
+}
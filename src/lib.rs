@@ -45,6 +45,8 @@
 
 extern crate libc;
 extern crate leveldb_sys;
+#[macro_use]
+extern crate log;
 
 use leveldb_sys::{leveldb_major_version, leveldb_minor_version};
 pub use database::options;
@@ -56,6 +58,16 @@ pub use database::kv;
 pub use database::batch;
 pub use database::management;
 pub use database::compaction;
+pub use database::properties;
+pub use database::sizes;
+pub use database::typed;
+pub use database::keyspace;
+pub use database::delete_range;
+pub use database::merge;
+pub use database::transaction;
+pub use database::indexed_batch;
+pub use database::changes;
+pub use database::backup;
 
 #[allow(missing_docs)]
 pub mod database;
@@ -0,0 +1,64 @@
+// Bridges leveldb's C++ Logger interface to callbacks implemented in Rust.
+//
+// The leveldb C API allows to set a `leveldb_logger_t`, but offers no way
+// to create one. This shim provides a Logger forwarding formatted messages
+// to Rust.
+#include <cstdarg>
+#include <cstddef>
+#include <cstdio>
+#include <vector>
+
+#include "leveldb/env.h"
+
+// The C API only declares `leveldb_logger_t`. Its definition in leveldb's
+// db/c.cc wraps the Logger and has been unchanged since the C API was added.
+struct leveldb_logger_t {
+  leveldb::Logger* rep;
+};
+
+namespace {
+
+typedef void (*log_fn)(void* state, const char* message, size_t length);
+typedef void (*destructor_fn)(void* state);
+
+class RustLogger : public leveldb::Logger {
+ public:
+  RustLogger(void* state, log_fn log, destructor_fn destructor)
+      : state_(state), log_(log), destructor_(destructor) {}
+
+  ~RustLogger() override { destructor_(state_); }
+
+  void Logv(const char* format, va_list ap) override {
+    char buffer[512];
+    va_list backup;
+    va_copy(backup, ap);
+    int length = vsnprintf(buffer, sizeof(buffer), format, ap);
+    if (length >= 0 && static_cast<size_t>(length) < sizeof(buffer)) {
+      log_(state_, buffer, length);
+    } else if (length >= 0) {
+      std::vector<char> large(length + 1);
+      vsnprintf(large.data(), large.size(), format, backup);
+      log_(state_, large.data(), length);
+    }
+    va_end(backup);
+  }
+
+ private:
+  void* state_;
+  log_fn log_;
+  destructor_fn destructor_;
+};
+
+}  // namespace
+
+extern "C" leveldb_logger_t* leveldb_rs_logger_create(void* state, log_fn log,
+                                                      destructor_fn destructor) {
+  leveldb_logger_t* logger = new leveldb_logger_t;
+  logger->rep = new RustLogger(state, log, destructor);
+  return logger;
+}
+
+extern "C" void leveldb_rs_logger_destroy(leveldb_logger_t* logger) {
+  delete logger->rep;
+  delete logger;
+}
@@ -0,0 +1,63 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::backup::Backup;
+use leveldb::compaction::Compaction;
+use leveldb::kv::KV;
+use leveldb::options::ReadOptions;
+use std::fs;
+
+#[test]
+fn test_backup_open_database() {
+    let tmp = tmpdir("backup");
+    let database = open_database(&tmp.path().join("db"), true);
+    for i in 0..100 {
+        db_put_simple(&database, i, &[i as u8]);
+    }
+    database.compact(&0, &50);
+    db_put_simple(&database, 100, &[100]);
+
+    database.backup(&tmp.path().join("backup")).unwrap();
+    db_put_simple(&database, 101, &[101]);
+
+    let backup = open_database::<i32>(&tmp.path().join("backup"), false);
+    assert_eq!(backup.get(ReadOptions::new(), 10).unwrap(), Some(vec![10]));
+    assert_eq!(backup.get(ReadOptions::new(), 100).unwrap(), Some(vec![100]));
+    assert_eq!(backup.get(ReadOptions::new(), 101).unwrap(), None);
+}
+
+#[test]
+fn test_backup_into_non_empty_directory() {
+    let tmp = tmpdir("backup_non_empty");
+    let database = open_database::<i32>(&tmp.path().join("db"), true);
+    fs::create_dir(tmp.path().join("backup")).unwrap();
+    fs::write(tmp.path().join("backup").join("file"), b"data").unwrap();
+    assert!(database.backup(&tmp.path().join("backup")).is_err());
+}
+
+#[test]
+fn test_incremental_backup() {
+    let tmp = tmpdir("backup_incremental");
+    let database = open_database(&tmp.path().join("db"), true);
+    for i in 0..100 {
+        db_put_simple(&database, i, &[i as u8]);
+    }
+    database.compact(&0, &100);
+    database.backup(&tmp.path().join("full")).unwrap();
+
+    db_put_simple(&database, 100, &[100]);
+    database.backup_incremental(&tmp.path().join("incremental"), &tmp.path().join("full")).unwrap();
+
+    let tables = |dir: &str| -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(tmp.path().join(dir)).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|n| n.ends_with(".ldb"))
+            .collect();
+        names.sort();
+        names
+    };
+    assert!(!tables("full").is_empty());
+    assert_eq!(tables("full"), tables("incremental"));
+
+    let backup = open_database::<i32>(&tmp.path().join("incremental"), false);
+    assert_eq!(backup.get(ReadOptions::new(), 50).unwrap(), Some(vec![50]));
+    assert_eq!(backup.get(ReadOptions::new(), 100).unwrap(), Some(vec![100]));
+}
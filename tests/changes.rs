@@ -0,0 +1,173 @@
+use utils::{open_database,tmpdir};
+use leveldb::batch::{Batch,BatchOp,Writebatch};
+use leveldb::changes::{ChangeListener,Changes,Changelog};
+use leveldb::error::{Error,ErrorKind,Operation};
+use leveldb::kv::KV;
+use leveldb::options::{ReadOptions,WriteOptions};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc,Mutex};
+use std::sync::atomic::{AtomicBool,Ordering};
+
+#[test]
+fn test_listeners_see_all_commits() {
+    let tmp = tmpdir("changes_listener");
+    let database = open_database(tmp.path(), true);
+    database.put(WriteOptions::new(), 0, &[0]).unwrap();
+
+    let seen = Arc::new(Mutex::new(vec![]));
+    let sink = seen.clone();
+    database.subscribe(move |sequence, batch: &Writebatch<i32>| {
+        sink.lock().unwrap().push((sequence, batch.ops().collect::<Vec<_>>()));
+    });
+
+    database.put(WriteOptions::new(), 1, &[1]).unwrap();
+    database.delete(WriteOptions::new(), 0).unwrap();
+    let mut batch = Writebatch::new();
+    batch.put(2, &[2]);
+    batch.put(3, &[3]);
+    database.write(WriteOptions::new(), &batch).unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), vec![
+        (1, vec![BatchOp::Put(1, vec![1])]),
+        (2, vec![BatchOp::Delete(0)]),
+        (3, vec![BatchOp::Put(2, vec![2]), BatchOp::Put(3, vec![3])]),
+    ]);
+    assert_eq!(database.last_sequence(), 3);
+}
+
+#[test]
+fn test_changelog_replay() {
+    let tmp = tmpdir("changes_changelog");
+    let log_path = tmp.path().join("changes.log");
+    {
+        let database = open_database(&tmp.path().join("primary"), true);
+        database.subscribe(Changelog::open(&log_path).unwrap());
+        database.put(WriteOptions::new(), 1, &[1]).unwrap();
+        database.put(WriteOptions::new(), 2, &[2]).unwrap();
+        database.delete(WriteOptions::new(), 1).unwrap();
+    }
+
+    let records = Changelog::read::<i32>(&log_path).unwrap();
+    assert_eq!(records.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    let follower = open_database(&tmp.path().join("follower"), true);
+    for (sequence, batch) in &records {
+        follower.write(WriteOptions::new(), batch).unwrap();
+        follower.set_last_sequence(*sequence);
+    }
+    assert_eq!(follower.get(ReadOptions::new(), 1).unwrap(), None);
+    assert_eq!(follower.get(ReadOptions::new(), 2).unwrap(), Some(vec![2]));
+    assert_eq!(follower.last_sequence(), 3);
+}
+
+#[test]
+fn test_changelog_continues_after_reopen() {
+    let tmp = tmpdir("changes_reopen");
+    let log_path = tmp.path().join("changes.log");
+    for i in 0..2 {
+        let database = open_database(&tmp.path().join("primary"), true);
+        database.subscribe(Changelog::open(&log_path).unwrap());
+        database.put(WriteOptions::new(), i, &[1]).unwrap();
+        database.put(WriteOptions::new(), i + 10, &[2]).unwrap();
+    }
+
+    let records = Changelog::read::<i32>(&log_path).unwrap();
+    assert_eq!(records.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_changelog_torn_tail() {
+    let tmp = tmpdir("changes_torn");
+    let log_path = tmp.path().join("changes.log");
+    {
+        let database = open_database(&tmp.path().join("primary"), true);
+        database.subscribe(Changelog::open(&log_path).unwrap());
+        database.put(WriteOptions::new(), 1, &[1]).unwrap();
+        database.put(WriteOptions::new(), 2, &[2]).unwrap();
+    }
+    let log = fs::read(&log_path).unwrap();
+
+    // a record cut short, as seen by a follower while it is being written
+    fs::write(&log_path, &log[..log.len() - 3]).unwrap();
+    let records = Changelog::read::<i32>(&log_path).unwrap();
+    assert_eq!(records.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1]);
+
+    // a complete header followed by a zero-filled body
+    let mut record = vec![];
+    record.extend_from_slice(&3u64.to_le_bytes());
+    record.extend_from_slice(&20u32.to_le_bytes());
+    record.extend_from_slice(&[0; 24]);
+    fs::write(&log_path, [&log[..], &record[..]].concat()).unwrap();
+    let records = Changelog::read::<i32>(&log_path).unwrap();
+    assert_eq!(records.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1, 2]);
+
+    // opening the log removes the damaged record and appends after it
+    let database = open_database(&tmp.path().join("primary"), true);
+    database.subscribe(Changelog::open(&log_path).unwrap());
+    database.put(WriteOptions::new(), 3, &[3]).unwrap();
+    let records = Changelog::read::<i32>(&log_path).unwrap();
+    assert_eq!(records.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+struct FailingListener;
+
+impl ChangeListener<i32> for FailingListener {
+    fn prepare(&self, _sequence: u64, _batch: &Writebatch<i32>) -> Result<(), Error> {
+        Err(Error::new_with_kind(ErrorKind::IoError, "disk full".to_string()))
+    }
+}
+
+#[test]
+fn test_failed_prepare_aborts_commit() {
+    let tmp = tmpdir("changes_abort");
+    let log_path = tmp.path().join("changes.log");
+    let database = open_database(&tmp.path().join("primary"), true);
+    database.subscribe(Changelog::open(&log_path).unwrap());
+    database.put(WriteOptions::new(), 1, &[1]).unwrap();
+    database.subscribe(FailingListener);
+
+    let err = database.put(WriteOptions::new(), 2, &[2]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IoError);
+    assert_eq!(err.operation(), Some(Operation::Put));
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+    assert_eq!(database.last_sequence(), 1);
+    // the record logged ahead of the failed commit is removed again
+    assert_eq!(Changelog::read::<i32>(&log_path).unwrap().len(), 1);
+}
+
+#[test]
+fn test_listener_cannot_write_to_its_database() {
+    let tmp = tmpdir("changes_reentrant");
+    let database = Arc::new(open_database(tmp.path(), true));
+    let weak = Arc::downgrade(&database);
+    let result = Arc::new(Mutex::new(None));
+    let sink = result.clone();
+    database.subscribe(move |_, _: &Writebatch<i32>| {
+        if let Some(database) = weak.upgrade() {
+            *sink.lock().unwrap() = Some(database.put(WriteOptions::new(), 2, &[2]));
+        }
+    });
+
+    database.put(WriteOptions::new(), 1, &[1]).unwrap();
+    let res = result.lock().unwrap().take().unwrap();
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+}
+
+#[test]
+fn test_panicking_listener() {
+    let tmp = tmpdir("changes_panic");
+    let database = open_database(tmp.path(), true);
+    let panicked = AtomicBool::new(false);
+    database.subscribe(move |_, _: &Writebatch<i32>| {
+        if !panicked.swap(true, Ordering::SeqCst) {
+            panic!("listener failed");
+        }
+    });
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| database.put(WriteOptions::new(), 1, &[1])));
+    assert!(res.is_err());
+    database.put(WriteOptions::new(), 2, &[2]).unwrap();
+    assert_eq!(database.last_sequence(), 2);
+}
@@ -1,14 +1,19 @@
 #[cfg(test)]
 mod comparator {
-  use libc::c_char;
   use key::Key;
   use utils::{tmpdir, db_put_simple};
   use leveldb::database::{Database};
-  use leveldb::iterator::Iterable;
+  use leveldb::iterator::{Iterable, LevelDBIterator};
   use leveldb::options::{Options,ReadOptions};
-  use leveldb::comparator::{Comparator,OrdComparator};
+  use leveldb::error::ErrorKind;
+  use leveldb::comparator::{Comparator,OrdComparator,ByteComparator,BytesComparator};
+  use leveldb::comparator::{Bytewise,ReverseBytewise,BigEndianUint,LengthThenBytes};
   use std::cmp::Ordering;
+  use std::ffi::CStr;
   use std::marker::PhantomData;
+  use std::env;
+  use std::panic::{self, AssertUnwindSafe};
+  use std::process::Command;
   
   struct ReverseComparator<K> {
       marker: PhantomData<K>
@@ -17,8 +22,8 @@ mod comparator {
   impl<K: Key + Ord> Comparator for ReverseComparator<K> {
     type K = K;
 
-    fn name(&self) -> *const c_char {
-      "reverse".as_ptr() as *const c_char
+    fn name(&self) -> &CStr {
+      CStr::from_bytes_with_nul(b"reverse\0").unwrap()
     }
   
     fn compare(&self, a: &K, b: &K) -> Ordering {
@@ -43,6 +48,26 @@ mod comparator {
     assert_eq!((1, vec![1]), iter.next().unwrap());
   }
 
+  #[test]
+  fn test_comparator_iterator_bounds() {
+    let comparator: ReverseComparator<i32> = ReverseComparator { marker: PhantomData };
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("reverse_comparator_bounds");
+    let database = &mut Database::open_with_comparator(tmp.path(), opts, comparator).unwrap();
+    db_put_simple(database, 1, &[1]);
+    db_put_simple(database, 2, &[2]);
+    db_put_simple(database, 3, &[3]);
+    db_put_simple(database, 4, &[4]);
+
+    let from = 3;
+    let to = 2;
+    let read_opts = ReadOptions::new();
+    let iter = database.keys_iter(read_opts).from(&from).to(&to);
+
+    assert_eq!(vec![3, 2], iter.collect::<Vec<_>>());
+  }
+
   #[test]
   fn test_ord_comparator() {
     let comparator: OrdComparator<i32> = OrdComparator::new("foo");
@@ -59,4 +84,140 @@ mod comparator {
     assert_eq!((1, vec![1]), iter.next().unwrap());
     assert_eq!((2, vec![2]), iter.next().unwrap());
   }
+
+  #[test]
+  fn test_open_with_comparator_turbofish() {
+    let comparator: OrdComparator<i32> = OrdComparator::new("foo");
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("comparator_turbofish");
+    let database = Database::open_with_comparator::<OrdComparator<i32>>(tmp.path(), opts, comparator);
+    assert!(database.is_ok());
+  }
+
+  struct PanickingComparator;
+
+  impl Comparator for PanickingComparator {
+    type K = i32;
+
+    fn name(&self) -> &CStr {
+      CStr::from_bytes_with_nul(b"panicking\0").unwrap()
+    }
+
+    fn compare(&self, _a: &i32, _b: &i32) -> Ordering {
+      panic!("compare failed");
+    }
+  }
+
+  // A panicking comparator aborts the process, so the test runs itself
+  // again in a child process and checks that the child aborted.
+  #[test]
+  fn test_comparator_panic() {
+    if env::var_os("LEVELDB_TEST_PANICKING_COMPARATOR").is_some() {
+      let mut opts = Options::new();
+      opts.create_if_missing = true;
+      let tmp = tmpdir("panicking_comparator");
+      let database = &mut Database::open_with_comparator(tmp.path(), opts, PanickingComparator).unwrap();
+      db_put_simple(database, 1, &[1]);
+      db_put_simple(database, 2, &[2]);
+      return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+      .args(&["comparator::comparator::test_comparator_panic", "--exact", "--nocapture"])
+      .env("LEVELDB_TEST_PANICKING_COMPARATOR", "1")
+      .output()
+      .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("compare failed"));
+  }
+
+  #[test]
+  fn test_comparator_panic_from_rust() {
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("panicking_comparator_bounds");
+    let database = &mut Database::open_with_comparator(tmp.path(), opts, PanickingComparator).unwrap();
+    // a single key is stored without comparing it to others
+    db_put_simple(database, 1, &[1]);
+
+    // checking the bound compares keys from Rust, which unwinds
+    let to = 2;
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+      database.iter(ReadOptions::new()).to(&to).count()
+    }));
+    let payload = res.err().expect("panic did not unwind");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"compare failed"));
+  }
+
+  #[test]
+  fn test_comparator_mismatch() {
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("comparator_mismatch");
+    {
+      let comparator: ReverseComparator<i32> = ReverseComparator { marker: PhantomData };
+      let database = &mut Database::open_with_comparator(tmp.path(), opts, comparator).unwrap();
+      db_put_simple(database, 1, &[1]);
+    }
+
+    let comparator: OrdComparator<i32> = OrdComparator::new("foo");
+    let res = Database::open_with_comparator(tmp.path(), Options::new(), comparator);
+    let err = res.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ComparatorMismatch);
+    assert!(err.message().contains("`reverse`"));
+    assert!(err.message().contains("`foo`"));
+
+    let res: Result<Database<i32>, _> = Database::open(tmp.path(), Options::new());
+    let err = res.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ComparatorMismatch);
+    assert!(err.message().contains("`leveldb.BytewiseComparator`"));
+  }
+
+  #[test]
+  fn test_bytes_comparator() {
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("bytes_comparator");
+    let database = &mut Database::open_with_comparator(tmp.path(), opts, BytesComparator::new(ReverseBytewise)).unwrap();
+    db_put_simple(database, 1, &[1]);
+    db_put_simple(database, 3, &[3]);
+    db_put_simple(database, 2, &[2]);
+
+    let read_opts = ReadOptions::new();
+    let keys: Vec<i32> = database.keys_iter(read_opts).collect();
+    assert_eq!(vec![3, 2, 1], keys);
+  }
+
+  #[test]
+  fn test_bytewise_opens_default_database() {
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let tmp = tmpdir("bytewise_comparator");
+    {
+      let database: &mut Database<i32> = &mut Database::open(tmp.path(), opts).unwrap();
+      db_put_simple(database, 1, &[1]);
+    }
+
+    let database = Database::open_with_comparator(tmp.path(), Options::new(), BytesComparator::new(Bytewise)).unwrap();
+    let read_opts = ReadOptions::new();
+    assert_eq!(vec![1], database.keys_iter(read_opts).collect::<Vec<i32>>());
+  }
+
+  #[test]
+  fn test_big_endian_uint() {
+    assert_eq!(BigEndianUint.compare(&[1, 0], &[2]), Ordering::Greater);
+    assert_eq!(BigEndianUint.compare(&[0, 0, 2], &[3]), Ordering::Less);
+    assert_eq!(BigEndianUint.compare(&[0, 5], &[5]), Ordering::Greater);
+    assert_eq!(BigEndianUint.compare(&[0, 5], &[0, 0, 4]), Ordering::Greater);
+    assert_eq!(BigEndianUint.compare(&[], &[0]), Ordering::Less);
+    assert_eq!(BigEndianUint.compare(&[0, 5], &[0, 5]), Ordering::Equal);
+  }
+
+  #[test]
+  fn test_length_then_bytes() {
+    assert_eq!(LengthThenBytes.compare(b"b", b"aa"), Ordering::Less);
+    assert_eq!(LengthThenBytes.compare(b"ab", b"aa"), Ordering::Greater);
+    assert_eq!(LengthThenBytes.compare(b"", b"a"), Ordering::Less);
+  }
 }
@@ -1,6 +1,7 @@
 use utils::{tmpdir};
 use leveldb::database::{Database};
 use leveldb::options::{Options};
+use leveldb::error::{ErrorKind,Operation};
 
 #[test]
 fn test_create_options() {
@@ -23,4 +24,42 @@ fn test_open_non_existant_database_without_create() {
   let tmp = tmpdir("missing");
   let res: Result<Database<i32>,_> = Database::open(tmp.path(), opts);
   assert!(res.is_err());
+  let error = res.err().unwrap();
+  assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+  assert_eq!(error.operation(), Some(Operation::Open));
+  assert_eq!(error.path(), Some(tmp.path()));
+}
+
+#[test]
+fn test_open_locked_database() {
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  let tmp = tmpdir("locked");
+  let _database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+  let res: Result<Database<i32>,_> = Database::open(tmp.path(), Options::new());
+  assert_eq!(res.err().unwrap().kind(), ErrorKind::LockHeld);
+}
+
+#[test]
+fn test_open_path_with_nul_byte() {
+  let tmp = tmpdir("nul_path");
+  let res: Result<Database<i32>,_> = Database::open(tmp.path().join("a\0b"), Options::new());
+  let error = res.err().unwrap();
+  assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+  assert_eq!(error.operation(), Some(Operation::Open));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_open_non_utf8_path() {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+
+  let tmp = tmpdir("non_utf8_path");
+  let path = tmp.path().join(OsStr::from_bytes(b"db-\xff"));
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  let res: Result<Database<i32>,_> = Database::open(&path, opts);
+  assert!(res.is_ok());
+  assert!(path.join("CURRENT").exists());
 }
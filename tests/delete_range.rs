@@ -0,0 +1,47 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::delete_range::{DeleteRange,DeleteRangeOptions};
+use leveldb::iterator::Iterable;
+use leveldb::options::ReadOptions;
+
+#[test]
+fn test_delete_range() {
+    let tmp = tmpdir("delete_range");
+    let database = &mut open_database(tmp.path(), true);
+    for i in 0..10 {
+        db_put_simple(database, i, &[i as u8]);
+    }
+
+    let deleted = database.delete_range(DeleteRangeOptions::new(), &2, &7).unwrap();
+    assert_eq!(deleted, 5);
+
+    let keys: Vec<i32> = database.keys_iter(ReadOptions::new()).collect();
+    assert_eq!(keys, vec![0, 1, 7, 8, 9]);
+}
+
+#[test]
+fn test_delete_range_chunked_and_compacted() {
+    let tmp = tmpdir("delete_range_chunked");
+    let database = &mut open_database(tmp.path(), true);
+    for i in 0..10 {
+        db_put_simple(database, i, &[i as u8]);
+    }
+
+    let mut options = DeleteRangeOptions::new();
+    options.chunk_size = Some(3);
+    options.compact = true;
+    let deleted = database.delete_range(options, &0, &8).unwrap();
+    assert_eq!(deleted, 8);
+
+    let keys: Vec<i32> = database.keys_iter(ReadOptions::new()).collect();
+    assert_eq!(keys, vec![8, 9]);
+}
+
+#[test]
+fn test_delete_empty_range() {
+    let tmp = tmpdir("delete_range_empty");
+    let database = &mut open_database(tmp.path(), true);
+    db_put_simple(database, 1, &[1]);
+
+    assert_eq!(database.delete_range(DeleteRangeOptions::new(), &2, &5).unwrap(), 0);
+    assert_eq!(database.keys_iter(ReadOptions::new()).count(), 1);
+}
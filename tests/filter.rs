@@ -0,0 +1,73 @@
+use utils::{tmpdir};
+use leveldb::database::{Database};
+use leveldb::options::{Options,ReadOptions,WriteOptions};
+use leveldb::database::kv::{KV};
+use leveldb::database::filter::{Filter,FilterPolicy};
+use leveldb::compaction::Compaction;
+use std::ffi::CStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize,Ordering};
+
+#[test]
+fn test_open_database_with_bloom_filter() {
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  opts.filter_policy = Some(Filter::bloom(10));
+  let tmp = tmpdir("bloom_filter");
+  let database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+
+  database.put(WriteOptions::new(), 1, &[1]).unwrap();
+  assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some(vec![1]));
+  assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+}
+
+struct ExactFilter {
+  lookups: Arc<AtomicUsize>,
+}
+
+impl FilterPolicy for ExactFilter {
+  fn name(&self) -> &CStr {
+    CStr::from_bytes_with_nul(b"exact\0").unwrap()
+  }
+
+  fn create_filter(&self, keys: &[&[u8]]) -> Vec<u8> {
+    let mut filter = vec![];
+    for key in keys {
+      filter.push(key.len() as u8);
+      filter.extend_from_slice(key);
+    }
+    filter
+  }
+
+  fn key_may_match(&self, key: &[u8], filter: &[u8]) -> bool {
+    self.lookups.fetch_add(1, Ordering::SeqCst);
+    let mut rest = filter;
+    while !rest.is_empty() {
+      let len = rest[0] as usize;
+      if &rest[1..len + 1] == key {
+        return true;
+      }
+      rest = &rest[len + 1..];
+    }
+    false
+  }
+}
+
+#[test]
+fn test_open_database_with_custom_filter() {
+  let lookups = Arc::new(AtomicUsize::new(0));
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  opts.filter_policy = Some(Filter::new(ExactFilter { lookups: lookups.clone() }));
+  let tmp = tmpdir("custom_filter");
+  let database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+
+  for i in 0..100 {
+    database.put(WriteOptions::new(), i * 2, &[1]).unwrap();
+  }
+  database.compact(&0, &200);
+
+  assert_eq!(database.get(ReadOptions::new(), 10).unwrap(), Some(vec![1]));
+  assert_eq!(database.get(ReadOptions::new(), 11).unwrap(), None);
+  assert!(lookups.load(Ordering::SeqCst) >= 2);
+}
@@ -0,0 +1,46 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::batch::Batch;
+use leveldb::indexed_batch::WritebatchWithIndex;
+use leveldb::kv::KV;
+use leveldb::options::{ReadOptions,WriteOptions};
+
+#[test]
+fn test_read_own_writes() {
+    let tmp = tmpdir("indexed_batch_get");
+    let database = open_database(tmp.path(), true);
+    db_put_simple(&database, 1, &[1]);
+    db_put_simple(&database, 2, &[2]);
+
+    let mut batch = WritebatchWithIndex::new(&database);
+    batch.put(3, &[3]);
+    batch.put(1, &[10]);
+    batch.delete(2);
+
+    assert_eq!(batch.get(ReadOptions::new(), 1).unwrap(), Some(vec![10]));
+    assert_eq!(batch.get(ReadOptions::new(), 2).unwrap(), None);
+    assert_eq!(batch.get(ReadOptions::new(), 3).unwrap(), Some(vec![3]));
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), Some(vec![2]));
+
+    database.write(WriteOptions::new(), batch.writebatch()).unwrap();
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some(vec![10]));
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+}
+
+#[test]
+fn test_iterate_with_pending_writes() {
+    let tmp = tmpdir("indexed_batch_iter");
+    let database = open_database(tmp.path(), true);
+    db_put_simple(&database, 1, &[1]);
+    db_put_simple(&database, 3, &[3]);
+    db_put_simple(&database, 5, &[5]);
+
+    let mut batch = WritebatchWithIndex::new(&database);
+    batch.put(0, &[0]);
+    batch.put(3, &[30]);
+    batch.put(4, &[4]);
+    batch.delete(5);
+    batch.put(6, &[6]);
+
+    let entries: Vec<(i32, Vec<u8>)> = batch.iter(ReadOptions::new()).collect();
+    assert_eq!(entries, vec![(0, vec![0]), (1, vec![1]), (3, vec![30]), (4, vec![4]), (6, vec![6])]);
+}
@@ -2,6 +2,9 @@ use utils::{open_database,tmpdir,db_put_simple};
 use leveldb::iterator::Iterable;
 use leveldb::iterator::LevelDBIterator;
 use leveldb::options::{ReadOptions};
+use leveldb::compaction::Compaction;
+use leveldb::error::{ErrorKind,Operation};
+use std::fs;
 
 #[test]
 fn test_iterator() {
@@ -84,3 +87,259 @@ fn test_value_iterator() {
   let value = iter.next().unwrap();
   assert_eq!(value, vec![1]);
 }
+
+#[test]
+fn test_iterator_reverse() {
+  let tmp = tmpdir("iter_reverse");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2]);
+  db_put_simple(database, 3, &[3]);
+
+  let read_opts = ReadOptions::new();
+  let iter = database.iter(read_opts);
+
+  assert_eq!(iter.rev().collect::<Vec<_>>(),
+             vec![(3, vec![3]), (2, vec![2]), (1, vec![1])]);
+}
+
+#[test]
+fn test_iterator_reverse_from_to() {
+  let tmp = tmpdir("iter_reverse_from_to");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2]);
+  db_put_simple(database, 4, &[4]);
+  db_put_simple(database, 5, &[5]);
+
+  let from = 2;
+  let to = 3;
+  let read_opts = ReadOptions::new();
+  let keys = database.keys_iter(read_opts).from(&from).to(&to);
+
+  assert_eq!(keys.rev().collect::<Vec<_>>(), vec![2]);
+}
+
+#[test]
+fn test_iterator_double_ended() {
+  let tmp = tmpdir("iter_double_ended");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2]);
+  db_put_simple(database, 3, &[3]);
+  db_put_simple(database, 4, &[4]);
+
+  let read_opts = ReadOptions::new();
+  let mut iter = database.value_iter(read_opts);
+
+  assert_eq!(iter.next(), Some(vec![1]));
+  assert_eq!(iter.next_back(), Some(vec![4]));
+  assert_eq!(iter.next_back(), Some(vec![3]));
+  assert_eq!(iter.next(), Some(vec![2]));
+  assert_eq!(iter.next(), None);
+  assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_iterator_prev() {
+  let tmp = tmpdir("iter_prev");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2]);
+
+  let read_opts = ReadOptions::new();
+  let mut iter = database.keys_iter(read_opts);
+
+  assert!(iter.prev());
+  assert_eq!(iter.key(), 2);
+  assert!(iter.prev());
+  assert_eq!(iter.key(), 1);
+  assert!(!iter.prev());
+}
+
+#[test]
+fn test_iterator_last_empty() {
+  let tmp = tmpdir("iter_last_empty");
+  let database = &mut open_database::<i32>(tmp.path(), true);
+
+  let read_opts = ReadOptions::new();
+  assert!(database.iter(read_opts).last().is_none());
+}
+
+#[test]
+fn test_iterator_to_bound() {
+  let tmp = tmpdir("iter_to_bound");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2]);
+  db_put_simple(database, 3, &[3]);
+  db_put_simple(database, 4, &[4]);
+
+  let from = 2;
+  let to = 3;
+  let read_opts = ReadOptions::new();
+  let keys = database.keys_iter(read_opts).from(&from).to(&to);
+  assert_eq!(keys.collect::<Vec<_>>(), vec![2, 3]);
+
+  let read_opts = ReadOptions::new();
+  let keys = database.keys_iter(read_opts).from(&from).to_exclusive(&to);
+  assert_eq!(keys.collect::<Vec<_>>(), vec![2]);
+
+  let read_opts = ReadOptions::new();
+  let keys = database.keys_iter(read_opts).to_exclusive(&to);
+  assert_eq!(keys.rev().collect::<Vec<_>>(), vec![2, 1]);
+}
+
+#[test]
+fn test_iterator_status() {
+  let tmp = tmpdir("iter_status");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+
+  let read_opts = ReadOptions::new();
+  let mut iter = database.iter(read_opts);
+  while iter.next().is_some() {}
+  assert!(iter.status().is_ok());
+}
+
+#[test]
+fn test_iterator_status_corruption() {
+  let tmp = tmpdir("iter_status_corruption");
+  {
+    let database = &mut open_database(tmp.path(), true);
+    for i in 0..1000 {
+      db_put_simple(database, i, &[7; 100]);
+    }
+    database.compact(&0, &1000);
+  }
+
+  for entry in fs::read_dir(tmp.path()).unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().map_or(false, |ext| ext == "ldb") {
+      let mut data = fs::read(&path).unwrap();
+      for byte in data[100..200].iter_mut() {
+        *byte = !*byte;
+      }
+      fs::write(&path, data).unwrap();
+    }
+  }
+
+  let database = &mut open_database::<i32>(tmp.path(), false);
+  let mut read_opts = ReadOptions::new();
+  read_opts.verify_checksums = true;
+  let mut iter = database.iter(read_opts);
+  while iter.next().is_some() {}
+  let error = iter.status().unwrap_err();
+  assert_eq!(error.kind(), ErrorKind::Corruption);
+  assert_eq!(error.operation(), Some(Operation::Iterate));
+  assert_eq!(error.path(), Some(tmp.path()));
+}
+
+#[test]
+fn test_iterator_borrowed_bytes() {
+  let tmp = tmpdir("iter_bytes");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  db_put_simple(database, 2, &[2, 2]);
+
+  let read_opts = ReadOptions::new();
+  let mut iter = database.iter(read_opts);
+
+  let mut values = 0;
+  while let Some((key, value)) = iter.next_bytes() {
+    assert_eq!(key.len(), 4);
+    values += value.len();
+  }
+  assert_eq!(values, 3);
+
+  assert_eq!(iter.next_back_bytes(), None);
+
+  let read_opts = ReadOptions::new();
+  let mut keys = database.keys_iter(read_opts);
+  assert!(keys.prev());
+  assert_eq!(keys.key_bytes(), &[0, 0, 0, 2]);
+  assert_eq!(keys.value_bytes(), &[2, 2]);
+}
+
+#[test]
+fn test_prefix_iterator() {
+  let tmp = tmpdir("prefix_iter");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 0x00ff, &[1]);
+  db_put_simple(database, 0x0100, &[2]);
+  db_put_simple(database, 0x01ff, &[3]);
+  db_put_simple(database, 0x0200, &[4]);
+
+  let read_opts = ReadOptions::new();
+  let keys: Vec<i32> = database.prefix_iter(read_opts, &[0, 0, 1]).map(|(k, _)| k).collect();
+  assert_eq!(keys, vec![0x0100, 0x01ff]);
+
+  let read_opts = ReadOptions::new();
+  let keys: Vec<i32> = database.prefix_iter(read_opts, &[0, 0, 1]).rev().map(|(k, _)| k).collect();
+  assert_eq!(keys, vec![0x01ff, 0x0100]);
+
+  let read_opts = ReadOptions::new();
+  assert_eq!(database.prefix_iter(read_opts, &[1]).count(), 0);
+}
+
+#[test]
+fn test_iterator_seek_ignores_bounds() {
+    let tmp = tmpdir("iter_seek");
+    let database = &mut open_database(tmp.path(), true);
+    for i in 1..6 {
+        db_put_simple(database, i, &[i as u8]);
+    }
+
+    let (from, to) = (2, 4);
+    let mut iter = database.iter(ReadOptions::new()).from(&from).to(&to);
+    iter.seek_to_first();
+    assert_eq!(iter.key(), 1);
+    iter.seek_to_last();
+    assert_eq!(iter.key(), 4);
+    iter.seek(&5);
+    assert_eq!(iter.key(), 5);
+
+    let mut iter = database.iter(ReadOptions::new());
+    iter.seek_to_last();
+    assert_eq!(iter.key(), 5);
+}
+
+#[test]
+fn test_iterator_continues_after_seek() {
+  let tmp = tmpdir("iter_seek_next");
+  let database = &mut open_database(tmp.path(), true);
+  for i in 1..6 {
+    db_put_simple(database, i, &[i as u8]);
+  }
+
+  let mut iter = database.keys_iter(ReadOptions::new());
+  iter.seek(&3);
+  assert_eq!(iter.next(), Some(3));
+  assert_eq!(iter.next(), Some(4));
+
+  iter.seek(&2);
+  assert_eq!(iter.next_back(), Some(2));
+  assert_eq!(iter.next_back(), Some(1));
+  assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_iterator_seek_after_exhaustion() {
+  let tmp = tmpdir("iter_reseek");
+  let database = &mut open_database(tmp.path(), true);
+  for i in 1..4 {
+    db_put_simple(database, i, &[i as u8]);
+  }
+
+  let mut iter = database.keys_iter(ReadOptions::new());
+  assert_eq!(iter.by_ref().count(), 3);
+  assert_eq!(iter.next(), None);
+
+  iter.seek_to_first();
+  assert_eq!(iter.next(), Some(1));
+  iter.seek_to_last();
+  assert_eq!(iter.next_back(), Some(3));
+  assert_eq!(iter.next_back(), Some(2));
+  iter.seek(&2);
+  assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3]);
+}
@@ -0,0 +1,139 @@
+use utils::{open_database,tmpdir};
+use leveldb::keyspace::Keyspace;
+use leveldb::kv::KV;
+use leveldb::batch::{Batch,Writebatch};
+use leveldb::iterator::{Iterable,LevelDBIterator};
+use leveldb::options::{Options,ReadOptions,WriteOptions};
+use leveldb::database::Database;
+use leveldb::comparator::{OrdComparator,BytesComparator,Bytewise};
+use leveldb::error::ErrorKind;
+
+#[test]
+fn test_tables_are_separate() {
+    let tmp = tmpdir("keyspace_separate");
+    let keyspace = Keyspace::new(open_database(tmp.path(), true)).unwrap();
+    let users = keyspace.table("users");
+    let orders = keyspace.table("orders");
+
+    users.put(WriteOptions::new(), 1, &[1]).unwrap();
+    orders.put(WriteOptions::new(), 1, &[2]).unwrap();
+    orders.put(WriteOptions::new(), 2, &[3]).unwrap();
+
+    assert_eq!(users.get(ReadOptions::new(), 1).unwrap(), Some(vec![1]));
+    assert_eq!(orders.get(ReadOptions::new(), 1).unwrap(), Some(vec![2]));
+    assert_eq!(users.get(ReadOptions::new(), 2).unwrap(), None);
+
+    orders.delete(WriteOptions::new(), 1).unwrap();
+    assert_eq!(users.get(ReadOptions::new(), 1).unwrap(), Some(vec![1]));
+    assert_eq!(orders.get(ReadOptions::new(), 1).unwrap(), None);
+}
+
+#[test]
+fn test_table_iteration() {
+    let tmp = tmpdir("keyspace_iter");
+    let keyspace = Keyspace::new(open_database(tmp.path(), true)).unwrap();
+    let a = keyspace.table("a");
+    let b = keyspace.table("b");
+    let c = keyspace.table("c");
+    a.put(WriteOptions::new(), 1, &[1]).unwrap();
+    for i in 1..5 {
+        b.put(WriteOptions::new(), i, &[i as u8]).unwrap();
+    }
+    c.put(WriteOptions::new(), 1, &[1]).unwrap();
+
+    let keys: Vec<i32> = b.keys_iter(ReadOptions::new()).collect();
+    assert_eq!(keys, vec![1, 2, 3, 4]);
+
+    let keys: Vec<i32> = b.keys_iter(ReadOptions::new()).rev().collect();
+    assert_eq!(keys, vec![4, 3, 2, 1]);
+
+    let from = 2;
+    let to = 3;
+    let entries: Vec<(i32, Vec<u8>)> = b.iter(ReadOptions::new()).from(&from).to(&to).collect();
+    assert_eq!(entries, vec![(2, vec![2]), (3, vec![3])]);
+
+    let empty = keyspace.table("empty");
+    assert_eq!(empty.keys_iter(ReadOptions::new()).count(), 0);
+    assert_eq!(empty.keys_iter(ReadOptions::new()).rev().count(), 0);
+}
+
+#[test]
+fn test_batch_across_tables() {
+    let tmp = tmpdir("keyspace_batch");
+    let keyspace = Keyspace::new(open_database(tmp.path(), true)).unwrap();
+    let users = keyspace.table("users");
+    let orders = keyspace.table("orders");
+    users.put(WriteOptions::new(), 2, &[2]).unwrap();
+
+    let mut batch = Writebatch::new();
+    users.writebatch(&mut batch).put(1, &[1]);
+    users.writebatch(&mut batch).delete(2);
+    orders.writebatch(&mut batch).put(1, &[10]);
+    keyspace.write(WriteOptions::new(), &batch).unwrap();
+
+    assert_eq!(users.get(ReadOptions::new(), 1).unwrap(), Some(vec![1]));
+    assert_eq!(users.get(ReadOptions::new(), 2).unwrap(), None);
+    assert_eq!(orders.get(ReadOptions::new(), 1).unwrap(), Some(vec![10]));
+}
+
+#[test]
+fn test_table_prefix_iteration() {
+    let tmp = tmpdir("keyspace_prefix");
+    let keyspace = Keyspace::new(open_database(tmp.path(), true)).unwrap();
+    let a = keyspace.table("a");
+    let b = keyspace.table("b");
+    a.put(WriteOptions::new(), 0x0101, &[1]).unwrap();
+    a.put(WriteOptions::new(), 0x0201, &[2]).unwrap();
+    b.put(WriteOptions::new(), 0x0102, &[3]).unwrap();
+
+    let keys: Vec<i32> = a.prefix_iter(ReadOptions::new(), &[0, 0, 1]).map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![0x0101]);
+}
+
+#[test]
+fn test_keyspace_rejects_custom_comparator() {
+    let tmp = tmpdir("keyspace_comparator");
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let comparator: OrdComparator<i32> = OrdComparator::new("ord");
+    let database = Database::open_with_comparator(tmp.path(), opts, comparator).unwrap();
+
+    let err = Keyspace::new(database).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_keyspace_accepts_bytewise_comparator() {
+    let tmp = tmpdir("keyspace_bytewise");
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    let comparator: BytesComparator<Bytewise, i32> = BytesComparator::new(Bytewise);
+    let database = Database::open_with_comparator(tmp.path(), opts, comparator).unwrap();
+
+    let keyspace = Keyspace::new(database).unwrap();
+    let table = keyspace.table("a");
+    table.put(WriteOptions::new(), 1, &[1]).unwrap();
+    assert_eq!(table.get(ReadOptions::new(), 1).unwrap(), Some(vec![1]));
+}
+
+#[test]
+fn test_table_seek() {
+    let tmp = tmpdir("keyspace_seek");
+    let keyspace = Keyspace::new(open_database(tmp.path(), true)).unwrap();
+    let a = keyspace.table("a");
+    let b = keyspace.table("b");
+    let c = keyspace.table("c");
+    a.put(WriteOptions::new(), 1, &[1]).unwrap();
+    for i in 1..4 {
+        b.put(WriteOptions::new(), i, &[i as u8]).unwrap();
+    }
+    c.put(WriteOptions::new(), 1, &[1]).unwrap();
+
+    let mut iter = b.iter(ReadOptions::new());
+    iter.seek_to_first();
+    assert_eq!(iter.key(), 1);
+    iter.seek_to_last();
+    assert_eq!(iter.key(), 3);
+    iter.seek(&2);
+    assert_eq!(iter.key(), 2);
+}
@@ -0,0 +1,50 @@
+use utils::{tmpdir};
+use leveldb::database::{Database};
+use leveldb::options::{Options,WriteOptions};
+use leveldb::database::kv::{KV};
+use leveldb::error::ErrorKind;
+use leveldb::database::logger::{Logger};
+use leveldb::compaction::Compaction;
+use std::sync::{Arc,Mutex};
+use std::sync::atomic::{AtomicBool,Ordering};
+
+#[test]
+fn test_open_database_with_logger() {
+  let messages = Arc::new(Mutex::new(vec![]));
+  let received = messages.clone();
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  opts.info_log = Some(Logger::new(move |message| {
+    received.lock().unwrap().push(message.to_string())
+  }));
+  let tmp = tmpdir("logger");
+  let database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+  database.put(WriteOptions::new(), 1, &[1]).unwrap();
+  database.compact(&0, &2);
+  drop(database);
+
+  assert!(!messages.lock().unwrap().is_empty());
+  assert!(!tmp.path().join("LOG").exists());
+}
+
+#[test]
+fn test_panicking_logger() {
+  let armed = Arc::new(AtomicBool::new(false));
+  let trigger = armed.clone();
+  let mut opts = Options::new();
+  opts.create_if_missing = true;
+  opts.info_log = Some(Logger::new(move |_| {
+    if trigger.load(Ordering::SeqCst) {
+      panic!("logger failed");
+    }
+  }));
+  let tmp = tmpdir("panicking_logger");
+  let database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+  database.put(WriteOptions::new(), 1, &[1]).unwrap();
+  armed.store(true, Ordering::SeqCst);
+  database.compact(&0, &2);
+
+  let err = database.put(WriteOptions::new(), 2, &[2]).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Panicked);
+  assert!(err.to_string().contains("logger failed"));
+}
@@ -32,3 +32,9 @@ fn test_repair_database() {
 //    assert!(res.is_err());
 //    drop(database);
 //}
+
+#[test]
+fn test_repair_path_with_nul_byte() {
+    let res = repair("a\0b", Options::new());
+    assert!(res.is_err());
+}
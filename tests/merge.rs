@@ -0,0 +1,65 @@
+use utils::{open_database,tmpdir};
+use leveldb::database::Database;
+use leveldb::kv::KV;
+use leveldb::merge::{Merge,MergeOperator};
+use leveldb::options::{Options,ReadOptions,WriteOptions};
+use std::sync::Arc;
+use std::thread;
+
+struct Add;
+
+impl MergeOperator for Add {
+    fn merge(&self, _key: &[u8], existing: Option<&[u8]>, operand: &[u8]) -> Option<Vec<u8>> {
+        let existing = existing.map(|v| v[0]).unwrap_or(0);
+        Some(vec![existing + operand[0]])
+    }
+}
+
+#[test]
+fn test_update() {
+    let tmp = tmpdir("merge_update");
+    let database = open_database(tmp.path(), true);
+
+    let value = database.update(WriteOptions::new(), 1, |v| {
+        assert_eq!(v, None);
+        Some(vec![1])
+    }).unwrap();
+    assert_eq!(value, Some(vec![1]));
+
+    database.update(WriteOptions::new(), 1, |v| v.map(|v| vec![v[0] * 5])).unwrap();
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some(vec![5]));
+
+    database.update(WriteOptions::new(), 1, |_| None).unwrap();
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), None);
+}
+
+#[test]
+fn test_concurrent_merges() {
+    let tmp = tmpdir("merge_concurrent");
+    let mut opts = Options::new();
+    opts.create_if_missing = true;
+    opts.merge_operator = Some(Box::new(Add));
+    let database: Database<i32> = Database::open(tmp.path(), opts).unwrap();
+    let shared = Arc::new(database);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let local_db = shared.clone();
+        thread::spawn(move || {
+            for _ in 0..25 {
+                local_db.merge(WriteOptions::new(), 1, &[1]).unwrap();
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(shared.get(ReadOptions::new(), 1).unwrap(), Some(vec![100]));
+}
+
+#[test]
+fn test_merge_without_operator() {
+    let tmp = tmpdir("merge_no_operator");
+    let database = open_database(tmp.path(), true);
+    assert!(database.merge(WriteOptions::new(), 1, &[1]).is_err());
+}
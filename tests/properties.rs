@@ -0,0 +1,34 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::properties::Properties;
+use leveldb::compaction::Compaction;
+
+#[test]
+fn test_unknown_property() {
+    let tmp = tmpdir("property_unknown");
+    let database = open_database::<i32>(tmp.path(), true);
+    assert!(database.property("leveldb.unknown").is_none());
+    assert!(database.property("leveldb.num-files-at-level100").is_none());
+}
+
+#[test]
+fn test_properties() {
+    let tmp = tmpdir("properties");
+    let database = &mut open_database(tmp.path(), true);
+    for i in 0..100 {
+        db_put_simple(database, i, &[1; 100]);
+    }
+    assert!(database.approximate_memory_usage().unwrap() > 0);
+
+    database.compact(&0, &100);
+
+    let sstables = database.sstables().unwrap();
+    assert_eq!(sstables.len(), 1);
+    let table = &sstables[0];
+    assert!(table.size > 0);
+
+    assert_eq!(database.num_files_at_level(table.level), Some(1));
+
+    let stats = database.stats().unwrap();
+    let level = stats.iter().find(|s| s.level == table.level).unwrap();
+    assert_eq!(level.files, 1);
+}
@@ -0,0 +1,18 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::sizes::ApproximateSizes;
+use leveldb::compaction::Compaction;
+
+#[test]
+fn test_approximate_sizes() {
+    let tmp = tmpdir("approximate_sizes");
+    let database = &mut open_database(tmp.path(), true);
+    for i in 0..1000 {
+        db_put_simple(database, i, &[1; 100]);
+    }
+    database.compact(&0, &1000);
+
+    let sizes = database.approximate_sizes(&[(0, 1000), (2000, 3000)]);
+    assert_eq!(sizes.len(), 2);
+    assert!(sizes[0] > 0);
+    assert_eq!(sizes[1], 0);
+}
@@ -30,3 +30,15 @@ fn test_snapshot_iterator() {
   let next = iter.next();
   assert_eq!(None, next);
 }
+
+#[test]
+fn test_snapshot_prefix_iterator() {
+  let tmp = tmpdir("snap_prefix_iterator");
+  let database = &mut open_database(tmp.path(), true);
+  db_put_simple(database, 1, &[1]);
+  let snapshot = database.snapshot();
+  db_put_simple(database, 2, &[2]);
+  let read_opts = ReadOptions::new();
+  let keys: Vec<i32> = snapshot.prefix_iter(read_opts, &[0, 0, 0]).map(|(k, _)| k).collect();
+  assert_eq!(vec![1], keys);
+}
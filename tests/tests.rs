@@ -1,7 +1,6 @@
 extern crate db_key as key;
 extern crate leveldb;
 extern crate tempdir;
-extern crate libc;
 
 mod utils;
 mod database;
@@ -10,7 +9,19 @@ mod binary;
 mod iterator;
 mod snapshots;
 mod cache;
+mod filter;
+mod logger;
 mod writebatch;
 mod management;
 mod compaction;
+mod properties;
+mod sizes;
+mod typed;
+mod keyspace;
+mod delete_range;
+mod merge;
+mod transaction;
+mod indexed_batch;
+mod changes;
+mod backup;
 mod concurrent_access;
\ No newline at end of file
@@ -0,0 +1,67 @@
+use utils::{open_database,tmpdir,db_put_simple};
+use leveldb::kv::KV;
+use leveldb::transaction::{Transactions,TransactionError};
+use leveldb::options::{ReadOptions,WriteOptions};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_commit() {
+    let tmp = tmpdir("transaction_commit");
+    let database = open_database(tmp.path(), true);
+    db_put_simple(&database, 1, &[1]);
+
+    let mut tx = database.transaction();
+    assert_eq!(tx.get(1).unwrap(), Some(vec![1]));
+    tx.put(2, &[2]);
+    tx.delete(1);
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+    tx.commit(WriteOptions::new()).unwrap();
+
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), None);
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), Some(vec![2]));
+}
+
+#[test]
+fn test_conflict() {
+    let tmp = tmpdir("transaction_conflict");
+    let database = open_database(tmp.path(), true);
+    db_put_simple(&database, 1, &[1]);
+
+    let mut tx = database.transaction();
+    assert_eq!(tx.get(1).unwrap(), Some(vec![1]));
+    db_put_simple(&database, 1, &[5]);
+    assert_eq!(tx.get(1).unwrap(), Some(vec![1]));
+    tx.put(2, &[2]);
+
+    match tx.commit(WriteOptions::new()) {
+        Err(TransactionError::Conflict) => {}
+        _ => panic!("expected a conflict"),
+    }
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+}
+
+#[test]
+fn test_transact_retries() {
+    let tmp = tmpdir("transaction_retries");
+    let database = Arc::new(open_database(tmp.path(), true));
+    db_put_simple(&database, 1, &[0]);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let local_db = database.clone();
+        thread::spawn(move || {
+            for _ in 0..10 {
+                local_db.transact(WriteOptions::new(), 1000, |tx| {
+                    let value = tx.get(1)?.unwrap();
+                    tx.put(1, &[value[0] + 1]);
+                    Ok(())
+                }).unwrap();
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some(vec![40]));
+}
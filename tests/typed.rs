@@ -0,0 +1,65 @@
+use utils::{open_database,tmpdir};
+use leveldb::typed::{TypedDatabase,TypedWritebatch};
+use leveldb::options::{ReadOptions,WriteOptions};
+use leveldb::error::{ErrorKind,Operation};
+use leveldb::kv::KV;
+
+#[test]
+fn test_typed_put_get() {
+    let tmp = tmpdir("typed_put_get");
+    let database: TypedDatabase<i32, String> = TypedDatabase::new(open_database(tmp.path(), true));
+    database.put(WriteOptions::new(), 1, &"one".to_string()).unwrap();
+
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some("one".to_string()));
+    assert_eq!(database.get(ReadOptions::new(), 2).unwrap(), None);
+
+    database.delete(WriteOptions::new(), 1).unwrap();
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), None);
+}
+
+#[test]
+fn test_typed_writebatch_and_iterator() {
+    let tmp = tmpdir("typed_batch");
+    let database: TypedDatabase<i32, u64> = TypedDatabase::new(open_database(tmp.path(), true));
+    let mut batch = TypedWritebatch::new();
+    batch.put(1, &10);
+    batch.put(2, &20);
+    batch.put(3, &30);
+    batch.delete(2);
+    database.write(WriteOptions::new(), &batch).unwrap();
+
+    let entries: Vec<(i32, u64)> = database.iter(ReadOptions::new()).collect();
+    assert_eq!(entries, vec![(1, 10), (3, 30)]);
+
+    let values: Vec<u64> = database.value_iter(ReadOptions::new()).rev().collect();
+    assert_eq!(values, vec![30, 10]);
+}
+
+#[test]
+fn test_typed_undecodable_value() {
+    let tmp = tmpdir("typed_undecodable");
+    let database = open_database(tmp.path(), true);
+    database.put(WriteOptions::new(), 1, &[0, 0, 0, 1]).unwrap();
+    database.put(WriteOptions::new(), 2, &[0xff]).unwrap();
+    database.put(WriteOptions::new(), 3, &[0, 0, 0, 3]).unwrap();
+    let database: TypedDatabase<i32, u32> = TypedDatabase::new(database);
+
+    assert_eq!(database.get(ReadOptions::new(), 1).unwrap(), Some(1));
+    let err = database.get(ReadOptions::new(), 2).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Corruption);
+    assert_eq!(err.operation(), Some(Operation::Get));
+
+    {
+        let mut iter = database.iter(ReadOptions::new());
+        assert_eq!(iter.next(), Some((1, 1)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.status().unwrap_err().kind(), ErrorKind::Corruption);
+    }
+
+    let strings: TypedDatabase<i32, String> = TypedDatabase::new(database.into_inner());
+    assert_eq!(strings.get(ReadOptions::new(), 2).unwrap_err().kind(), ErrorKind::Corruption);
+    let mut values = strings.value_iter(ReadOptions::new());
+    assert_eq!(values.next(), Some("\u{0}\u{0}\u{0}\u{1}".to_string()));
+    assert_eq!(values.next(), None);
+    assert!(values.status().is_err());
+}
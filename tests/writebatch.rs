@@ -2,7 +2,8 @@ use utils::{tmpdir};
 use leveldb::database::{Database};
 use leveldb::options::{Options,ReadOptions,WriteOptions};
 use leveldb::database::kv::{KV};
-use leveldb::database::batch::{Batch,Writebatch,WritebatchIterator};
+use leveldb::database::batch::{Batch,BatchOp,Writebatch,WritebatchIterator};
+use std::panic::{self, AssertUnwindSafe};
 
 #[test]
 fn test_writebatch() {
@@ -78,3 +79,104 @@ fn test_writebatchiter() {
     assert_eq!(iter2.put, 2);
     assert_eq!(iter2.deleted, 1);
 }
+
+#[test]
+fn test_writebatch_introspection() {
+  let mut batch = Writebatch::new();
+  assert!(batch.is_empty());
+  assert_eq!(batch.len(), 0);
+  assert_eq!(batch.approximate_size(), 12);
+
+  batch.put(1, &[1, 2]);
+  batch.delete(2);
+  assert!(!batch.is_empty());
+  assert_eq!(batch.len(), 2);
+  // tag, key length, 4 key bytes, value length, 2 value bytes;
+  // then tag, key length, 4 key bytes
+  assert_eq!(batch.approximate_size(), 12 + 9 + 6);
+
+  let ops: Vec<BatchOp<i32>> = batch.ops().collect();
+  assert_eq!(ops, vec![BatchOp::Put(1, vec![1, 2]), BatchOp::Delete(2)]);
+}
+
+#[test]
+fn test_writebatch_append() {
+  let mut batch = Writebatch::new();
+  batch.put(1, &[1]);
+  let mut other = Writebatch::new();
+  other.put(2, &[2]);
+  other.delete(1);
+
+  batch.append(&other);
+  assert_eq!(batch.len(), 3);
+  assert_eq!(other.len(), 2);
+  assert_eq!(batch.approximate_size(), batch.to_bytes().len());
+  let ops: Vec<BatchOp<i32>> = batch.ops().collect();
+  assert_eq!(ops, vec![BatchOp::Put(1, vec![1]), BatchOp::Put(2, vec![2]), BatchOp::Delete(1)]);
+
+  batch.clear();
+  assert!(batch.is_empty());
+  assert_eq!(batch.approximate_size(), 12);
+}
+
+#[test]
+fn test_writebatch_serialization() {
+  let mut batch = Writebatch::new();
+  batch.put(1, &[1, 2]);
+  batch.delete(2);
+
+  let bytes = batch.to_bytes();
+  assert_eq!(bytes.len(), batch.approximate_size());
+  assert_eq!(&bytes[..12], &[0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+  assert_eq!(&bytes[12..], &[1, 4, 0, 0, 0, 1, 2, 1, 2, 0, 4, 0, 0, 0, 2]);
+
+  let copy: Writebatch<i32> = Writebatch::from_bytes(&bytes).unwrap();
+  assert_eq!(copy.ops().collect::<Vec<_>>(), batch.ops().collect::<Vec<_>>());
+  assert_eq!(copy.to_bytes(), bytes);
+}
+
+#[test]
+fn test_writebatch_from_malformed_bytes() {
+  assert!(Writebatch::<i32>::from_bytes(&[0; 4]).is_err());
+  // the header claims one operation, but there is none
+  assert!(Writebatch::<i32>::from_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]).is_err());
+  // truncated key
+  assert!(Writebatch::<i32>::from_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 4, 0]).is_err());
+  // unknown tag
+  assert!(Writebatch::<i32>::from_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 7, 0]).is_err());
+}
+
+struct PanickingIter {
+    put: i32,
+}
+
+impl WritebatchIterator for PanickingIter {
+    type K = i32;
+
+    fn put(&mut self,
+           _key: i32,
+           _value: &[u8]) {
+        self.put = self.put + 1;
+        panic!("put callback failed");
+    }
+
+    fn deleted(&mut self,
+               _key: i32) {
+    }
+}
+
+#[test]
+fn test_writebatchiter_panic() {
+    let batch = &mut Writebatch::new();
+    batch.put(1, &[1]);
+    batch.put(2, &[2]);
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        batch.iterate(Box::new(PanickingIter { put: 0 }))
+    }));
+    let payload = res.err().expect("panic was not re-raised");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"put callback failed"));
+
+    // the batch stays usable afterwards
+    assert_eq!(batch.iterate(Box::new(Iter { put: 0, deleted: 0 })).put, 2);
+}